@@ -1,17 +1,268 @@
-use calimero_sdk::app;
 use calimero_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use calimero_sdk::serde::{Deserialize, Serialize};
+use calimero_sdk::{app, env};
 use calimero_storage::collections::{StoreError, UnorderedMap};
+use sha2::{Digest, Sha256};
+use std::io;
 use std::str::FromStr;
+use team_state::{Board, CellConflict, CellContent, Electorate, Operation, OperationKind, Proposal, ProposalStatus, Quorum, TeamError, TeamGrid, Voting, VotingSettings};
+use thiserror::Error;
 
-#[app::state]
-#[derive(Default, BorshSerialize, BorshDeserialize)]
+/// Longest time a member can keep a cell for themselves
+const MAX_CLAIM_MS: u64 = 60_000;
+/// Roles allowed to edit the grid and vote
+const SOLVERS: &[Role] = &[Role::Captain, Role::Solver];
+/// Every member can chat, spectators included
+const MEMBERS: &[Role] = &[Role::Captain, Role::Solver, Role::Spectator];
+const MAX_MESSAGE_LEN: usize = 1000;
+/// Editor address of the cells revealed by the server as hints, they count for nobody
+const HINT_EDITOR: &str = "hint";
+
+/// The 81 cells of a sudoku, valued from 1 to 9.
+pub struct Sudoku;
+
+impl Board for Sudoku {
+    const CELLS: u8 = 81;
+
+    fn is_valid_value(value: u8) -> bool {
+        (1..=9).contains(&value)
+    }
+}
+
+#[app::state(emits = for<'a> Event<'a>)]
+#[derive(Default, BorshSerialize)]
 #[borsh(crate = "calimero_sdk::borsh")]
 struct BattleState {
-    /// table[position] = (value, editor)
-    table: UnorderedMap<String, (u8, String, String)>,
+    // Layout of the first version of the application, kept first so its contexts still load
+    /// Legacy cells, moved into `grid` by `migrate_grid`
+    table: UnorderedMap<String, CellContent>,
     removed_cells: Vec<(u8, String, String)>,
     vote_public_solution: Vec<String>,
-    vote_private_solution: Vec<String>
+    vote_private_solution: Vec<String>,
+    // Added since, missing from the contexts of the first version
+    /// The grid with its givens and the history of its changes
+    grid: TeamGrid<Sudoku>,
+    claims: UnorderedMap<String, CellClaim>,
+    voting: Voting,
+    /// Identity which created the context, the server of the battle. Empty for the contexts of
+    /// the first version until `migrate_grid` is called.
+    admin: String,
+    /// members[identity], identities being the base58 public keys executing the calls
+    members: UnorderedMap<String, Member>,
+    /// Team chat, oldest first
+    messages: Vec<ChatMessage>,
+    /// notes[position] = pencil marks of the members on an empty cell
+    notes: UnorderedMap<String, Vec<CellNote>>,
+    /// admissions[address] = hash of the token the server gave to the player of `address` to
+    /// join with it, see `admit`
+    admissions: UnorderedMap<String, String>,
+}
+
+/// Reads both layouts: the state of a context of the first version ends after the votes, its
+/// other fields start empty.
+impl BorshDeserialize for BattleState {
+    fn deserialize_reader<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let table = BorshDeserialize::deserialize_reader(reader)?;
+        let removed_cells = BorshDeserialize::deserialize_reader(reader)?;
+        let vote_public_solution = BorshDeserialize::deserialize_reader(reader)?;
+        let vote_private_solution = BorshDeserialize::deserialize_reader(reader)?;
+
+        let mut rest = vec![];
+        reader.read_to_end(&mut rest)?;
+        if rest.is_empty() {
+            return Ok(Self {
+                table,
+                removed_cells,
+                vote_public_solution,
+                vote_private_solution,
+                ..Self::default()
+            });
+        }
+        let rest = &mut rest.as_slice();
+        let state = Self {
+            table,
+            removed_cells,
+            vote_public_solution,
+            vote_private_solution,
+            grid: BorshDeserialize::deserialize(rest)?,
+            claims: BorshDeserialize::deserialize(rest)?,
+            voting: BorshDeserialize::deserialize(rest)?,
+            admin: BorshDeserialize::deserialize(rest)?,
+            members: BorshDeserialize::deserialize(rest)?,
+            messages: BorshDeserialize::deserialize(rest)?,
+            notes: BorshDeserialize::deserialize(rest)?,
+            // contexts created before the admissions
+            admissions: if rest.is_empty() { UnorderedMap::new() } else { BorshDeserialize::deserialize(rest)? },
+        };
+        if !rest.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "unexpected bytes after the battle state"));
+        }
+        Ok(state)
+    }
+}
+
+#[derive(Clone, BorshSerialize, BorshDeserialize, Serialize)]
+#[borsh(crate = "calimero_sdk::borsh")]
+#[serde(crate = "calimero_sdk::serde")]
+pub struct ChatMessage {
+    pub id: usize,
+    pub author_address: String,
+    pub author_name: String,
+    pub text: String,
+    /// Cell the message is about
+    pub position: Option<u8>,
+    /// Milliseconds since the epoch
+    pub time: u64,
+}
+
+/// Pencil marks of one member on a cell.
+#[derive(Clone, BorshSerialize, BorshDeserialize, Serialize)]
+#[borsh(crate = "calimero_sdk::borsh")]
+#[serde(crate = "calimero_sdk::serde")]
+pub struct CellNote {
+    pub position: u8,
+    pub owner_address: String,
+    pub owner_name: String,
+    /// Values the member considers for the cell, sorted
+    pub candidates: Vec<u8>,
+    pub text: Option<String>,
+    /// Milliseconds since the epoch
+    pub time: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[borsh(crate = "calimero_sdk::borsh")]
+#[serde(crate = "calimero_sdk::serde")]
+pub enum Role {
+    /// Manages the roles and the settings of the team, there is always one
+    Captain,
+    /// Edits the grid and votes
+    Solver,
+    /// Only follows the game
+    Spectator,
+}
+
+#[derive(Clone, BorshSerialize, BorshDeserialize, Serialize)]
+#[borsh(crate = "calimero_sdk::borsh")]
+#[serde(crate = "calimero_sdk::serde")]
+pub struct Member {
+    pub identity: String,
+    /// Principal of the player in the battle, recorded as the editor of their cells
+    pub address: String,
+    pub name: String,
+    pub role: Role,
+}
+
+#[derive(Clone, BorshSerialize, BorshDeserialize, Serialize)]
+#[borsh(crate = "calimero_sdk::borsh")]
+#[serde(crate = "calimero_sdk::serde")]
+pub struct CellClaim {
+    pub position: u8,
+    pub editor_address: String,
+    pub editor_name: String,
+    /// Milliseconds since the epoch
+    pub expires_at: u64,
+}
+
+/// Events of the team, for the members and the server to follow the game without polling.
+#[app::event]
+pub enum Event<'a> {
+    /// `editor` is the identity of the member who made the change
+    CellSet {
+        position: u8,
+        value: u8,
+        editor: &'a str,
+        kind: OperationKind,
+        version: u64,
+    },
+    CellRemoved {
+        position: u8,
+        editor: &'a str,
+        kind: OperationKind,
+        version: u64,
+    },
+    /// A vote with `vote_solution` when `proposal_id` is not set, `voter` being an identity
+    VoteCast {
+        voter: &'a str,
+        proposal_id: Option<usize>,
+        public: bool,
+        approve: bool,
+    },
+    VoteWithdrawn {
+        voter: &'a str,
+        proposal_id: usize,
+    },
+    ProposalFinalized {
+        proposal_id: usize,
+        public: bool,
+        grid_hash: &'a str,
+    },
+    ChatMessage {
+        id: usize,
+        author_address: &'a str,
+        text: &'a str,
+        position: Option<u8>,
+    },
+}
+
+#[derive(Debug, Error, Serialize)]
+#[serde(crate = "calimero_sdk::serde")]
+#[serde(tag = "kind", content = "data")]
+pub enum Error {
+    #[error("cell {position} is claimed by {editor_address}")]
+    CellClaimed { position: u8, editor_address: String },
+    #[error("{0} has not joined the team")]
+    NotMember(String),
+    #[error("a {0:?} cannot do that")]
+    Forbidden(Role),
+    #[error("only the server of the battle can do that")]
+    NotAdmin,
+    #[error("the team must keep a captain")]
+    CaptainRequired,
+    #[error("messages and notes are limited to {MAX_MESSAGE_LEN} bytes")]
+    MessageTooLong,
+    #[error("the member already plays with {0}")]
+    AddressLocked(String),
+    #[error("{0} was not admitted to the team by the server")]
+    NotAdmitted(String),
+    #[error("another member already plays with {0}")]
+    AddressClaimed(String),
+    #[error("the server must call migrate_grid first")]
+    NotMigrated,
+}
+
+/// A value repeated in a row, a column or a box of the grid.
+#[derive(Clone, BorshSerialize, BorshDeserialize, Serialize)]
+#[borsh(crate = "calimero_sdk::borsh")]
+#[serde(crate = "calimero_sdk::serde")]
+pub struct Duplicate {
+    pub unit: Unit,
+    /// Index of the row, column or box, from 0 to 8
+    pub index: u8,
+    pub value: u8,
+    pub positions: Vec<u8>,
+}
+
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, Serialize)]
+#[borsh(crate = "calimero_sdk::borsh")]
+#[serde(crate = "calimero_sdk::serde")]
+pub enum Unit {
+    Row,
+    Column,
+    Box,
+}
+
+impl Unit {
+    fn positions(self, index: u8) -> [u8; 9] {
+        std::array::from_fn(|i| {
+            let i = i as u8;
+            match self {
+                Unit::Row => index * 9 + i,
+                Unit::Column => i * 9 + index,
+                Unit::Box => (index / 3 * 3 + i / 3) * 9 + index % 3 * 3 + i % 3,
+            }
+        })
+    }
 }
 
 #[app::logic]
@@ -23,49 +274,536 @@ impl BattleState {
             removed_cells: vec![],
             vote_public_solution: vec![],
             vote_private_solution: vec![],
+            grid: TeamGrid::default(),
+            claims: UnorderedMap::new(),
+            voting: Voting::default(),
+            admin: executor(),
+            members: UnorderedMap::new(),
+            messages: vec![],
+            notes: UnorderedMap::new(),
+        }
+    }
+
+    /// Posts a message to the team, about a cell if `position` is set.
+    pub fn send_message(&mut self, text: String, position: Option<u8>) -> app::Result<ChatMessage> {
+        let author = self.require(MEMBERS)?;
+        check_text(&text)?;
+        if let Some(position) = position {
+            TeamGrid::<Sudoku>::check_cell(position, None)?;
+        }
+        let message = ChatMessage {
+            id: self.messages.len(),
+            author_address: author.address,
+            author_name: author.name,
+            text,
+            position,
+            time: now_ms(),
+        };
+        app::emit!(Event::ChatMessage {
+            id: message.id,
+            author_address: &message.author_address,
+            text: &message.text,
+            position,
+        });
+        self.messages.push(message.clone());
+        Ok(message)
+    }
+
+    /// `limit` messages starting at id `from`.
+    pub fn get_messages(&self, from: usize, limit: usize) -> Vec<ChatMessage> {
+        self.messages.iter().skip(from).take(limit).cloned().collect()
+    }
+
+    /// Replaces the pencil marks of the executor on a cell. Marks without candidates nor text are
+    /// removed.
+    pub fn set_note(&mut self, position: u8, mut candidates: Vec<u8>, text: Option<String>) -> app::Result<Option<CellNote>> {
+        let owner = self.require(SOLVERS)?;
+        self.grid.check_editable(position, None)?;
+        for &value in &candidates {
+            TeamGrid::<Sudoku>::check_cell(position, Some(value))?;
+        }
+        if let Some(text) = &text {
+            check_text(text)?;
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let key = position.to_string();
+        let mut notes = self.notes.get(&key)?.unwrap_or_default();
+        notes.retain(|note| note.owner_address != owner.address);
+        let note = (!candidates.is_empty() || text.is_some()).then(|| CellNote {
+            position,
+            owner_address: owner.address,
+            owner_name: owner.name,
+            candidates,
+            text,
+            time: now_ms(),
+        });
+        notes.extend(note.clone());
+        if notes.is_empty() {
+            self.notes.remove(&key)?;
+        } else {
+            self.notes.insert(key, notes)?;
+        }
+        Ok(note)
+    }
+
+    /// Pencil marks of the cells from `from_position`, in grid order, for at most `limit` cells.
+    pub fn get_notes(&self, from_position: u8, limit: usize) -> Result<Vec<CellNote>, StoreError> {
+        let mut notes = vec![];
+        for position in (from_position..Sudoku::CELLS).take(limit) {
+            notes.extend(self.notes.get(&position.to_string())?.unwrap_or_default());
+        }
+        Ok(notes)
+    }
+
+    /// Registers the executor as a member of the team, with the principal they play the battle
+    /// with. A new member passes the token the server gave to that principal, so nobody joins
+    /// as another player, and no other member may play with it already. The first member is the
+    /// captain, the next ones solvers. Members joining again only update their name: the address
+    /// their cells are rewarded to cannot change.
+    pub fn join(&mut self, address: String, name: String, token: String) -> app::Result<Member> {
+        if self.admin.is_empty() {
+            app::bail!(Error::NotMigrated);
+        }
+        let identity = executor();
+        let admitted = self.admissions.get(&address)? == Some(token_hash(&token));
+        let role = match self.members.get(&identity)? {
+            Some(member) if member.address != address => app::bail!(Error::AddressLocked(member.address)),
+            Some(member) => member.role,
+            None if !admitted => app::bail!(Error::NotAdmitted(address)),
+            None => {
+                if self.members.entries()?.any(|(_, member)| member.address == address) {
+                    app::bail!(Error::AddressClaimed(address));
+                }
+                if self.members.len()? == 0 {
+                    Role::Captain
+                } else {
+                    Role::Solver
+                }
+            }
+        };
+        if admitted {
+            self.admissions.remove(&address)?;
+        }
+        let member = Member { identity: identity.clone(), address, name, role };
+        self.members.insert(identity, member.clone())?;
+        Ok(member)
+    }
+
+    /// Lets the player of `address` join with the token hashed into `token_hash`, replacing the
+    /// token given before. The server calls it for the players it authenticated.
+    pub fn admit(&mut self, address: String, token_hash: String) -> app::Result<()> {
+        if executor() != self.admin {
+            app::bail!(Error::NotAdmin);
         }
+        self.admissions.insert(address, token_hash)?;
+        Ok(())
     }
 
-    pub fn set_cell(&mut self, position: u8, value: u8, editor_address: String, editor_name: String) -> Result<(), StoreError>  {
-        self.table
-            .insert(position.to_string(), (value, editor_address, editor_name))?;
+    /// Changes the role of a member. Naming another captain makes the current one a solver.
+    pub fn set_role(&mut self, identity: String, role: Role) -> app::Result<Member> {
+        let mut captain = self.require(&[Role::Captain])?;
+        let Some(mut member) = self.members.get(&identity)? else {
+            app::bail!(Error::NotMember(identity));
+        };
+        if member.identity == captain.identity {
+            if role != Role::Captain {
+                app::bail!(Error::CaptainRequired);
+            }
+            return Ok(member);
+        }
+        if role == Role::Captain {
+            captain.role = Role::Solver;
+            self.members.insert(captain.identity.clone(), captain)?;
+        }
+        member.role = role;
+        self.members.insert(identity, member.clone())?;
+        Ok(member)
+    }
+
+    pub fn get_members(&self) -> Result<Vec<Member>, StoreError> {
+        Ok(self.members.entries()?.map(|(_, member)| member).collect())
+    }
+
+    /// Sets the puzzle of the battle once it started, its givens cannot be edited. Cells the
+    /// members filled on given positions before are emptied.
+    pub fn set_initial_state(&mut self, initial_state: Vec<(u8, u8)>) -> app::Result<()> {
+        if executor() != self.admin {
+            app::bail!(Error::NotAdmin);
+        }
+        let removals = self.grid.set_givens(initial_state, self.admin.clone(), now_ms())?;
+        removals.iter().for_each(emit);
         Ok(())
     }
 
-    pub fn remove_cell(&mut self, position: u8, editor_address: String, editor_name: String) -> Result<(), StoreError> {
-        self.table.remove(&position.to_string())?;
-        self.removed_cells.push((position, editor_address, editor_name));
+    pub fn get_initial_state(&self) -> Vec<(u8, u8)> {
+        self.grid.givens().to_vec()
+    }
+
+    /// Writes a cell. `seen_version` is the version of the cell the editor saw before editing,
+    /// a write over a newer version by someone else is logged as a conflict.
+    pub fn set_cell(&mut self, position: u8, value: u8, seen_version: Option<u64>) -> app::Result<u64> {
+        let editor = self.require(SOLVERS)?;
+        let claims = &self.claims;
+        let address = editor.address.clone();
+        let op = self.grid.set(position, (value, editor.address, editor.name), editor.identity, seen_version, now_ms(), |position| check_claim(claims, position, &address))?;
+        emit(&op);
+        Ok(op.version)
+    }
+
+    pub fn remove_cell(&mut self, position: u8, seen_version: Option<u64>) -> app::Result<u64> {
+        let editor = self.require(SOLVERS)?;
+        let claims = &self.claims;
+        let op = self.grid.remove(position, editor.identity, seen_version, now_ms(), |position| check_claim(claims, position, &editor.address))?;
+        emit(&op);
+        self.removed_cells.push((position, editor.address, editor.name));
+        Ok(op.version)
+    }
+
+    /// Writes the value of the solution the server revealed as a hint, over any claim.
+    pub fn reveal_cell(&mut self, position: u8, value: u8) -> app::Result<u64> {
+        if executor() != self.admin {
+            app::bail!(Error::NotAdmin);
+        }
+        let content = (value, HINT_EDITOR.to_string(), "Hint".to_string());
+        let op = self.grid.set(position, content, self.admin.clone(), None, now_ms(), |_| Ok::<_, TeamError>(()))?;
+        emit(&op);
+        Ok(op.version)
+    }
+
+    /// Reverts the last change of the executor which is not undone yet, unless a teammate
+    /// changed the cell since.
+    pub fn undo(&mut self) -> app::Result<Operation> {
+        let editor = self.require(SOLVERS)?;
+        let claims = &self.claims;
+        let op = self.grid.undo(editor.identity, now_ms(), |position| check_claim(claims, position, &editor.address))?;
+        emit(&op);
+        Ok(op)
+    }
+
+    /// Applies again the last operation the executor undid.
+    pub fn redo(&mut self) -> app::Result<Operation> {
+        let editor = self.require(SOLVERS)?;
+        let claims = &self.claims;
+        let op = self.grid.redo(editor.identity, now_ms(), |position| check_claim(claims, position, &editor.address))?;
+        emit(&op);
+        Ok(op)
+    }
+
+    /// `limit` operations of the history starting at `from`.
+    pub fn get_history(&self, from: usize, limit: usize) -> Vec<Operation> {
+        self.grid.history(from, limit)
+    }
+
+    /// Brings the grid back to how it was right after the operation at `index`. The cells which
+    /// differ are written again, so the replay is itself part of the history and can be undone.
+    pub fn replay_to(&mut self, index: usize) -> app::Result<Vec<Operation>> {
+        let editor = self.require(SOLVERS)?;
+        let claims = &self.claims;
+        let replay = self.grid.replay_to(index, editor.identity, now_ms(), |position| check_claim(claims, position, &editor.address))?;
+        replay.iter().for_each(emit);
+        Ok(replay)
+    }
+
+    /// Keeps teammates from editing a cell for `duration_ms`, or until the claim is released.
+    pub fn claim_cell(&mut self, position: u8, duration_ms: u64) -> app::Result<CellClaim> {
+        let editor = self.require(SOLVERS)?;
+        self.grid.check_editable(position, None)?;
+        check_claim(&self.claims, position, &editor.address)?;
+        let claim = CellClaim {
+            position,
+            editor_address: editor.address,
+            editor_name: editor.name,
+            expires_at: now_ms() + duration_ms.min(MAX_CLAIM_MS),
+        };
+        self.claims.insert(position.to_string(), claim.clone())?;
+        Ok(claim)
+    }
+
+    pub fn release_cell(&mut self, position: u8) -> app::Result<()> {
+        let editor = self.require(SOLVERS)?;
+        check_claim(&self.claims, position, &editor.address)?;
+        self.claims.remove(&position.to_string())?;
         Ok(())
     }
 
-    pub fn get_cell(&self, position: u8) -> Option<(u8, String, String)> {
-        self.table.get(&position.to_string()).unwrap()
+    /// Claims which have not expired yet.
+    pub fn get_claims(&self) -> Result<Vec<CellClaim>, StoreError> {
+        let now = now_ms();
+        Ok(self.claims.entries()?.map(|(_, claim)| claim).filter(|claim| claim.expires_at > now).collect())
+    }
+
+    pub fn get_cell(&self, position: u8) -> app::Result<Option<(u8, String, String)>> {
+        Ok(self.grid.cell(position)?)
+    }
+
+    /// Version of a cell, to send back as `seen_version` when editing it. 0 if it was never written.
+    pub fn get_cell_version(&self, position: u8) -> app::Result<u64> {
+        Ok(self.grid.version(position)?)
     }
 
     pub fn get_removed_cells(&self) -> Vec<(u8, String, String)> {
         self.removed_cells.clone()
     }
 
-    pub fn get_current_solution(&self) -> Vec<(u8, u8, String, String)> {
-        self.table
-            .entries()
-            .unwrap()
-            .map(|(position, (value, editor, name))| (u8::from_str(&position).unwrap(), value, editor, name))
-            .collect()
+    pub fn get_edit_conflicts(&self) -> Vec<CellConflict> {
+        self.grid.conflicts().to_vec()
+    }
+
+    /// Values repeated in a row, a column or a box of the grid, givens included.
+    pub fn get_conflicts(&self) -> Vec<Duplicate> {
+        let grid = self.grid.values();
+
+        let mut duplicates = vec![];
+        for unit in [Unit::Row, Unit::Column, Unit::Box] {
+            for index in 0..9 {
+                let positions = unit.positions(index);
+                for value in 1..=9 {
+                    let repeated: Vec<u8> = positions.iter().copied().filter(|&p| grid[p as usize] == value).collect();
+                    if repeated.len() > 1 {
+                        duplicates.push(Duplicate { unit, index, value, positions: repeated });
+                    }
+                }
+            }
+        }
+        duplicates
+    }
+
+    pub fn get_current_solution(&self) -> app::Result<Vec<(u8, u8, String, String)>> {
+        Ok(self
+            .grid
+            .filled()?
+            .into_iter()
+            .map(|(position, (value, editor, name))| (position, value, editor, name))
+            .collect())
+    }
+
+    /// Moves the cells of the legacy `table` into the grid. Cells the grid refuses, because
+    /// their position is given or already written, stay in `table`, so running it again only
+    /// retries them. A context of the first version has no admin yet: the server calls this
+    /// right after updating its application and becomes its admin.
+    pub fn migrate_grid(&mut self) -> app::Result<usize> {
+        if self.admin.is_empty() {
+            self.admin = executor();
+        }
+        if executor() != self.admin {
+            app::bail!(Error::NotAdmin);
+        }
+        let mut moved = 0;
+        for (key, cell) in self.table.entries()?.collect::<Vec<_>>() {
+            let Ok(position) = u8::from_str(&key) else {
+                continue;
+            };
+            if self.grid.restore(position, cell).unwrap_or(false) {
+                self.table.remove(&key)?;
+                moved += 1;
+            }
+        }
+        Ok(moved)
     }
-    
-    pub fn vote_solution(&mut self, public: bool, caller: String) {
+
+    pub fn vote_solution(&mut self, public: bool) -> app::Result<()> {
+        let caller = self.require(SOLVERS)?.identity;
         let votes = if public {
             &mut self.vote_public_solution
         } else {
             &mut self.vote_private_solution
         };
         if !votes.contains(&caller) {
+            app::emit!(Event::VoteCast { voter: &caller, proposal_id: None, public, approve: true });
             votes.push(caller);
         }
+        Ok(())
     }
-    
+
     pub fn get_vote_solution(&self) -> (Vec<String>, Vec<String>) {
         (self.vote_public_solution.clone(), self.vote_private_solution.clone())
     }
+
+    /// The size of the team is the number of solvers, captain included, when a vote is cast.
+    pub fn set_voting(&mut self, quorum: Quorum) -> app::Result<()> {
+        self.require(&[Role::Captain])?;
+        self.voting.configure(VotingSettings { quorum });
+        Ok(())
+    }
+
+    pub fn get_voting(&self) -> VotingSettings {
+        self.voting.settings().clone()
+    }
+
+    /// Proposes to submit the current grid, publicly or with a proof. The proposer approves it
+    /// and the open proposals are superseded.
+    pub fn propose_solution(&mut self, public: bool) -> app::Result<Proposal> {
+        let proposer = self.require(SOLVERS)?.identity;
+        let electorate = self.electorate()?;
+        let proposal = self.voting.propose(public, proposer, self.grid.hash(), now_ms(), &electorate)?;
+        emit_vote(&proposal, &proposal.proposer, true);
+        Ok(proposal)
+    }
+
+    /// Approves or rejects a proposal, replacing any earlier vote of the executor on it.
+    pub fn vote_proposal(&mut self, id: usize, approve: bool) -> app::Result<Proposal> {
+        let voter = self.require(SOLVERS)?.identity;
+        let electorate = self.electorate()?;
+        let proposal = self.voting.vote(id, voter.clone(), approve, &self.grid.hash(), &electorate)?;
+        emit_vote(&proposal, &voter, approve);
+        Ok(proposal)
+    }
+
+    pub fn withdraw_vote(&mut self, id: usize) -> app::Result<Proposal> {
+        let voter = self.require(SOLVERS)?.identity;
+        let proposal = self.voting.withdraw(id, &voter, &self.grid.hash())?;
+        app::emit!(Event::VoteWithdrawn { voter: &voter, proposal_id: id });
+        Ok(proposal)
+    }
+
+    /// `limit` proposals starting at `from`.
+    pub fn get_proposals(&self, from: usize, limit: usize) -> Vec<Proposal> {
+        self.voting.proposals(from, limit)
+    }
+
+    /// The latest proposal which reached the quorum, if the grid did not change since.
+    pub fn get_finalized_proposal(&self) -> Option<Proposal> {
+        self.voting.finalized(&self.grid.hash()).cloned()
+    }
+
+    /// The member executing the call, if their role is one of `roles`.
+    fn require(&self, roles: &[Role]) -> app::Result<Member> {
+        let identity = executor();
+        let Some(member) = self.members.get(&identity)? else {
+            app::bail!(Error::NotMember(identity));
+        };
+        if !roles.contains(&member.role) {
+            app::bail!(Error::Forbidden(member.role));
+        }
+        Ok(member)
+    }
+
+    /// Identities of the members allowed to vote, as their roles are now.
+    fn electorate(&self) -> Result<Electorate, StoreError> {
+        let mut electorate = Electorate::default();
+        for (identity, member) in self.members.entries()? {
+            if member.role == Role::Captain {
+                electorate.captain = Some(identity.clone());
+            }
+            if SOLVERS.contains(&member.role) {
+                electorate.voters.push(identity);
+            }
+        }
+        Ok(electorate)
+    }
+}
+
+fn check_claim(claims: &UnorderedMap<String, CellClaim>, position: u8, editor_address: &str) -> app::Result<()> {
+    if let Some(claim) = claims.get(&position.to_string())? {
+        if claim.expires_at > now_ms() && claim.editor_address != editor_address {
+            app::bail!(Error::CellClaimed { position, editor_address: claim.editor_address });
+        }
+    }
+    Ok(())
+}
+
+fn emit(op: &Operation) {
+    match &op.value {
+        Some((value, _, _)) => app::emit!(Event::CellSet {
+            position: op.position,
+            value: *value,
+            editor: &op.editor,
+            kind: op.kind,
+            version: op.version,
+        }),
+        None => app::emit!(Event::CellRemoved {
+            position: op.position,
+            editor: &op.editor,
+            kind: op.kind,
+            version: op.version,
+        }),
+    }
+}
+
+fn emit_vote(proposal: &Proposal, voter: &str, approve: bool) {
+    app::emit!(Event::VoteCast { voter, proposal_id: Some(proposal.id), public: proposal.public, approve });
+    if proposal.status == ProposalStatus::Finalized {
+        app::emit!(Event::ProposalFinalized { proposal_id: proposal.id, public: proposal.public, grid_hash: &proposal.grid_hash });
+    }
+}
+
+fn check_text(text: &str) -> app::Result<()> {
+    if text.len() > MAX_MESSAGE_LEN {
+        app::bail!(Error::MessageTooLong);
+    }
+    Ok(())
+}
+
+/// Hex encoded SHA-256 of an admission token.
+fn token_hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Base58 public key of the identity executing the call.
+fn executor() -> String {
+    bs58::encode(env::executor_id()).into_string()
+}
+
+fn now_ms() -> u64 {
+    env::time_now() / 1_000_000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calimero_sdk::borsh::{from_slice, to_vec};
+
+    /// `BattleState` as the first version of the application stored it.
+    #[derive(BorshSerialize)]
+    #[borsh(crate = "calimero_sdk::borsh")]
+    struct LegacyBattleState {
+        table: UnorderedMap<String, (u8, String, String)>,
+        removed_cells: Vec<(u8, String, String)>,
+        vote_public_solution: Vec<String>,
+        vote_private_solution: Vec<String>,
+    }
+
+    #[test]
+    fn test_legacy_state() {
+        let mut table = UnorderedMap::new();
+        table.insert("3".to_string(), (5, "player".to_string(), "Alice".to_string())).unwrap();
+        let legacy = LegacyBattleState {
+            table,
+            removed_cells: vec![(4, "player".to_string(), "Alice".to_string())],
+            vote_public_solution: vec!["player".to_string()],
+            vote_private_solution: vec![],
+        };
+
+        let state: BattleState = from_slice(&to_vec(&legacy).unwrap()).unwrap();
+        assert_eq!(state.table.get(&"3".to_string()).unwrap(), Some((5, "player".to_string(), "Alice".to_string())));
+        assert_eq!(state.removed_cells, legacy.removed_cells);
+        assert_eq!(state.vote_public_solution, legacy.vote_public_solution);
+        assert!(state.admin.is_empty());
+        assert_eq!(state.members.len().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_state_layout() {
+        let mut state = BattleState {
+            admin: "server".to_string(),
+            ..BattleState::default()
+        };
+        state.vote_private_solution.push("player".to_string());
+
+        let read: BattleState = from_slice(&to_vec(&state).unwrap()).unwrap();
+        assert_eq!(read.admin, "server");
+        assert_eq!(read.vote_private_solution, state.vote_private_solution);
+
+        let mut bytes = to_vec(&state).unwrap();
+        // a context created before the admissions
+        let before_admissions = &bytes[..bytes.len() - to_vec(&state.admissions).unwrap().len()];
+        let read: BattleState = from_slice(before_admissions).unwrap();
+        assert_eq!(read.admin, "server");
+        assert_eq!(read.admissions.len().unwrap(), 0);
+
+        bytes.push(0);
+        assert!(from_slice::<BattleState>(&bytes).is_err());
+    }
 }
@@ -0,0 +1,29 @@
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "data")]
+pub enum TeamError {
+    #[error("position {0} is outside of the board")]
+    InvalidPosition(u8),
+    #[error("value {0} cannot be written on the board")]
+    InvalidValue(u8),
+    #[error("cell {0} is given by the puzzle")]
+    GivenCell(u8),
+    #[error("the puzzle was already set")]
+    PuzzleAlreadySet,
+    #[error("cell {0} refers to an unknown editor")]
+    UnknownEditor(u8),
+    #[error("nothing to {0}")]
+    NothingTo(&'static str),
+    #[error("cell {position} was changed by {editor} since")]
+    CellChanged { position: u8, editor: String },
+    #[error("the history has no operation {0}")]
+    UnknownOperation(usize),
+    #[error("there is no proposal {0}")]
+    UnknownProposal(usize),
+    #[error("proposal {0} is closed")]
+    ProposalClosed(usize),
+    #[error("the board changed since proposal {0}")]
+    ProposalOutdated(usize),
+}
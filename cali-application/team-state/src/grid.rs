@@ -0,0 +1,575 @@
+use crate::error::TeamError;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+/// (value, editor address, editor name) of a filled cell
+pub type CellContent = (u8, String, String);
+
+/// Shape of the board of a game.
+pub trait Board {
+    /// Number of cells, positions go from 0 to `CELLS - 1`
+    const CELLS: u8;
+
+    fn is_valid_value(value: u8) -> bool;
+}
+
+#[derive(Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize)]
+struct Cell {
+    value: u8,
+    /// Index in `editors`
+    editor: u32,
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+struct Editor {
+    address: String,
+    name: String,
+}
+
+/// A change of one cell of the board.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, Serialize)]
+pub struct Operation {
+    pub kind: OperationKind,
+    pub position: u8,
+    /// Content of the cell before the operation, `None` if it was empty
+    pub previous: Option<CellContent>,
+    /// Content of the cell after the operation, `None` if it was emptied
+    pub value: Option<CellContent>,
+    /// Identity of the member who made the change, which only they can undo
+    pub editor: String,
+    /// Milliseconds since the epoch
+    pub time: u64,
+    pub version: u64,
+    /// Set while the operation is undone
+    pub undone: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize, Serialize)]
+#[serde(tag = "type", content = "index")]
+pub enum OperationKind {
+    Set,
+    Remove,
+    /// Reverts the operation at `index` in the history
+    Undo(usize),
+    Redo(usize),
+    /// Brings the cell back to its content right after the operation at `index`
+    Replay(usize),
+}
+
+/// A value overwritten by a teammate who had not seen it.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, Serialize)]
+pub struct CellConflict {
+    pub position: u8,
+    /// Value lost by the write, `None` if the write emptied the cell
+    pub overwritten: Option<CellContent>,
+    pub written: Option<CellContent>,
+    /// Version of the cell the writer had seen
+    pub seen_version: u64,
+    pub version: u64,
+}
+
+/// The board of a team with the history of its changes.
+///
+/// Every change takes a `check` of the positions it writes, which lets the game refuse it
+/// (e.g. when a teammate claimed the cell) before anything is written.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct TeamGrid<B> {
+    /// (position, value) of the givens of the puzzle, empty until the game starts
+    givens: Vec<(u8, u8)>,
+    /// cells[position], `None` for an empty cell
+    cells: Vec<Option<Cell>>,
+    /// Members who edited the board, cells refer to them by index
+    editors: Vec<Editor>,
+    /// Logical clock, ticked by every write to the board
+    clock: u64,
+    /// versions[position] = clock of the last write to the cell
+    versions: Vec<u64>,
+    conflicts: Vec<CellConflict>,
+    /// Every change of the board, in order
+    history: Vec<Operation>,
+    /// Indexes in `history` of the undone operations which can be redone, latest last
+    redo_stack: Vec<usize>,
+    #[borsh(skip)]
+    board: PhantomData<B>,
+}
+
+impl<B: Board> Default for TeamGrid<B> {
+    fn default() -> Self {
+        Self {
+            givens: vec![],
+            cells: vec![None; B::CELLS as usize],
+            editors: vec![],
+            clock: 0,
+            versions: vec![0; B::CELLS as usize],
+            conflicts: vec![],
+            history: vec![],
+            redo_stack: vec![],
+            board: PhantomData,
+        }
+    }
+}
+
+impl<B: Board> TeamGrid<B> {
+    pub fn check_cell(position: u8, value: Option<u8>) -> Result<(), TeamError> {
+        if position >= B::CELLS {
+            return Err(TeamError::InvalidPosition(position));
+        }
+        match value {
+            Some(value) if !B::is_valid_value(value) => Err(TeamError::InvalidValue(value)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the givens of the puzzle once, they cannot be edited afterwards. The cells members
+    /// filled before on given positions are emptied as `editor`, returns these removals.
+    pub fn set_givens(&mut self, givens: Vec<(u8, u8)>, editor: String, now: u64) -> Result<Vec<Operation>, TeamError> {
+        if !self.givens.is_empty() {
+            return Err(TeamError::PuzzleAlreadySet);
+        }
+        for &(position, value) in &givens {
+            Self::check_cell(position, Some(value))?;
+        }
+        let mut removals = vec![];
+        for &(position, _) in &givens {
+            if self.cells[position as usize].is_some() {
+                removals.push(self.apply(OperationKind::Remove, position, None, editor.clone(), None, now)?);
+            }
+        }
+        self.givens = givens;
+        Ok(removals)
+    }
+
+    pub fn givens(&self) -> &[(u8, u8)] {
+        &self.givens
+    }
+
+    /// Fails if `value` cannot be written in `position` by a member.
+    pub fn check_editable(&self, position: u8, value: Option<u8>) -> Result<(), TeamError> {
+        Self::check_cell(position, value)?;
+        if self.givens.iter().any(|&(given, _)| given == position) {
+            return Err(TeamError::GivenCell(position));
+        }
+        Ok(())
+    }
+
+    /// Content of a cell with the address and name of its editor.
+    pub fn cell(&self, position: u8) -> Result<Option<CellContent>, TeamError> {
+        Self::check_cell(position, None)?;
+        let Some(cell) = self.cells[position as usize] else {
+            return Ok(None);
+        };
+        let editor = self
+            .editors
+            .get(cell.editor as usize)
+            .ok_or(TeamError::UnknownEditor(position))?;
+        Ok(Some((cell.value, editor.address.clone(), editor.name.clone())))
+    }
+
+    /// Version of a cell, to send back as `seen_version` when editing it. 0 if it was never
+    /// written.
+    pub fn version(&self, position: u8) -> Result<u64, TeamError> {
+        Self::check_cell(position, None)?;
+        Ok(self.versions[position as usize])
+    }
+
+    /// The cells filled by the members, in board order.
+    pub fn filled(&self) -> Result<Vec<(u8, CellContent)>, TeamError> {
+        let mut filled = vec![];
+        for position in 0..B::CELLS {
+            if let Some(content) = self.cell(position)? {
+                filled.push((position, content));
+            }
+        }
+        Ok(filled)
+    }
+
+    /// Values of the cells, givens included, 0 for an empty cell.
+    pub fn values(&self) -> Vec<u8> {
+        let mut values = vec![0; B::CELLS as usize];
+        for &(position, value) in &self.givens {
+            values[position as usize] = value;
+        }
+        for (position, cell) in self.cells.iter().enumerate() {
+            if let Some(cell) = cell {
+                values[position] = cell.value;
+            }
+        }
+        values
+    }
+
+    /// Hex encoded SHA-256 of [`Self::values`].
+    pub fn hash(&self) -> String {
+        Sha256::digest(self.values()).iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    pub fn conflicts(&self) -> &[CellConflict] {
+        &self.conflicts
+    }
+
+    /// `limit` operations of the history starting at `from`.
+    pub fn history(&self, from: usize, limit: usize) -> Vec<Operation> {
+        self.history.iter().skip(from).take(limit).cloned().collect()
+    }
+
+    /// Writes a cell as `editor`. `seen_version` is the version of the cell the editor saw before
+    /// editing, a write over a newer version by someone else is logged as a conflict.
+    pub fn set<E: From<TeamError>>(
+        &mut self,
+        position: u8,
+        content: CellContent,
+        editor: String,
+        seen_version: Option<u64>,
+        now: u64,
+        check: impl Fn(u8) -> Result<(), E>,
+    ) -> Result<Operation, E> {
+        self.check_editable(position, Some(content.0))?;
+        check(position)?;
+        self.forget_redo(&editor);
+        Ok(self.apply(OperationKind::Set, position, Some(content), editor, seen_version, now)?)
+    }
+
+    pub fn remove<E: From<TeamError>>(
+        &mut self,
+        position: u8,
+        editor: String,
+        seen_version: Option<u64>,
+        now: u64,
+        check: impl Fn(u8) -> Result<(), E>,
+    ) -> Result<Operation, E> {
+        self.check_editable(position, None)?;
+        check(position)?;
+        self.forget_redo(&editor);
+        Ok(self.apply(OperationKind::Remove, position, None, editor, seen_version, now)?)
+    }
+
+    /// Reverts the last change of `editor` which is not undone yet, unless a teammate changed
+    /// the cell since.
+    pub fn undo<E: From<TeamError>>(
+        &mut self,
+        editor: String,
+        now: u64,
+        check: impl Fn(u8) -> Result<(), E>,
+    ) -> Result<Operation, E> {
+        let index = self
+            .history
+            .iter()
+            .rposition(|op| {
+                op.editor == editor
+                    && !op.undone
+                    && matches!(
+                        op.kind,
+                        OperationKind::Set | OperationKind::Remove | OperationKind::Redo(_) | OperationKind::Replay(_)
+                    )
+            })
+            .ok_or(TeamError::NothingTo("undo"))?;
+        let op = self.history[index].clone();
+        if self.cell(op.position)? != op.value {
+            return Err(self.changed(op.position).into());
+        }
+        // The givens may have been set since the operation
+        self.check_editable(op.position, op.previous.as_ref().map(|content| content.0))?;
+        check(op.position)?;
+        let undo = self.apply(OperationKind::Undo(index), op.position, op.previous, editor, None, now)?;
+        self.history[index].undone = true;
+        // Undoing a redo makes the original operation redoable again
+        let redone = match op.kind {
+            OperationKind::Redo(redone) => redone,
+            _ => index,
+        };
+        self.history[redone].undone = true;
+        self.redo_stack.push(redone);
+        Ok(undo)
+    }
+
+    /// Applies again the last operation `editor` undid.
+    pub fn redo<E: From<TeamError>>(
+        &mut self,
+        editor: String,
+        now: u64,
+        check: impl Fn(u8) -> Result<(), E>,
+    ) -> Result<Operation, E> {
+        let stack_index = self
+            .redo_stack
+            .iter()
+            .rposition(|&index| self.history[index].editor == editor)
+            .ok_or(TeamError::NothingTo("redo"))?;
+        let index = self.redo_stack[stack_index];
+        let op = self.history[index].clone();
+        if self.cell(op.position)? != op.previous {
+            return Err(self.changed(op.position).into());
+        }
+        self.check_editable(op.position, op.value.as_ref().map(|content| content.0))?;
+        check(op.position)?;
+        self.redo_stack.remove(stack_index);
+        let redo = self.apply(OperationKind::Redo(index), op.position, op.value, editor, None, now)?;
+        self.history[index].undone = false;
+        Ok(redo)
+    }
+
+    /// Brings the board back to how it was right after the operation at `index`. The cells
+    /// which differ are written again, so the replay is itself part of the history.
+    pub fn replay_to<E: From<TeamError>>(
+        &mut self,
+        index: usize,
+        editor: String,
+        now: u64,
+        check: impl Fn(u8) -> Result<(), E>,
+    ) -> Result<Vec<Operation>, E> {
+        if index >= self.history.len() {
+            return Err(TeamError::UnknownOperation(index).into());
+        }
+        let mut board = BTreeMap::new();
+        for op in &self.history[..=index] {
+            board.insert(op.position, op.value.clone());
+        }
+        // Cells first written after the operation were empty at that point
+        for op in &self.history[index + 1..] {
+            board.entry(op.position).or_insert(None);
+        }
+
+        let mut changes = vec![];
+        for (position, content) in board {
+            if self.cell(position)? != content {
+                self.check_editable(position, content.as_ref().map(|content| content.0))?;
+                check(position)?;
+                changes.push((position, content));
+            }
+        }
+        let mut replay = vec![];
+        for (position, content) in changes {
+            replay.push(self.apply(OperationKind::Replay(index), position, content, editor.clone(), None, now)?);
+        }
+        self.forget_redo(&editor);
+        Ok(replay)
+    }
+
+    /// Writes an empty cell without recording it in the history, to import the board of an
+    /// older state layout. Returns whether the cell was written.
+    pub fn restore(&mut self, position: u8, content: CellContent) -> Result<bool, TeamError> {
+        self.check_editable(position, Some(content.0))?;
+        if self.cells[position as usize].is_some() {
+            return Ok(false);
+        }
+        self.put(position, Some(content));
+        Ok(true)
+    }
+
+    /// Writes a cell and records the operation in the history.
+    fn apply(
+        &mut self,
+        kind: OperationKind,
+        position: u8,
+        value: Option<CellContent>,
+        editor: String,
+        seen_version: Option<u64>,
+        now: u64,
+    ) -> Result<Operation, TeamError> {
+        let previous = self.cell(position)?;
+        self.clock += 1;
+        let version = self.clock;
+        if let Some(seen_version) = seen_version {
+            let same_editor = matches!((&previous, &value), (Some(old), Some(new)) if old.1 == new.1);
+            if seen_version < self.versions[position as usize] && !same_editor {
+                self.conflicts.push(CellConflict {
+                    position,
+                    overwritten: previous.clone(),
+                    written: value.clone(),
+                    seen_version,
+                    version,
+                });
+            }
+        }
+        self.versions[position as usize] = version;
+        self.put(position, value.clone());
+
+        let op = Operation {
+            kind,
+            position,
+            previous,
+            value,
+            editor,
+            time: now,
+            version,
+            undone: false,
+        };
+        self.history.push(op.clone());
+        Ok(op)
+    }
+
+    fn put(&mut self, position: u8, content: Option<CellContent>) {
+        self.cells[position as usize] = content.map(|(value, address, name)| {
+            let editor = Editor { address, name };
+            let index = match self.editors.iter().position(|e| *e == editor) {
+                Some(index) => index,
+                None => {
+                    self.editors.push(editor);
+                    self.editors.len() - 1
+                }
+            };
+            Cell {
+                value,
+                editor: index as u32,
+            }
+        });
+    }
+
+    /// A new change of an editor makes the changes they undid impossible to redo.
+    fn forget_redo(&mut self, editor: &str) {
+        let history = &self.history;
+        self.redo_stack.retain(|&index| history[index].editor != editor);
+    }
+
+    fn changed(&self, position: u8) -> TeamError {
+        let editor = self
+            .history
+            .iter()
+            .rev()
+            .find(|op| op.position == position)
+            .map(|op| op.editor.clone())
+            .unwrap_or_default();
+        TeamError::CellChanged { position, editor }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::TeamError;
+    use crate::grid::{Board, CellContent, OperationKind, TeamGrid};
+
+    /// A 2x2 board taking values from 1 to 4.
+    struct Tiny;
+
+    impl Board for Tiny {
+        const CELLS: u8 = 4;
+
+        fn is_valid_value(value: u8) -> bool {
+            (1..=4).contains(&value)
+        }
+    }
+
+    fn content(value: u8, editor: &str) -> CellContent {
+        (value, editor.to_string(), editor.to_uppercase())
+    }
+
+    fn ok(_: u8) -> Result<(), TeamError> {
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_and_remove() {
+        let mut grid = TeamGrid::<Tiny>::default();
+        grid.set_givens(vec![(0, 1)], "server".to_string(), 0).unwrap();
+        assert_eq!(grid.set_givens(vec![(1, 1)], "server".to_string(), 0), Err(TeamError::PuzzleAlreadySet));
+
+        assert_eq!(grid.set(0, content(2, "alice"), "alice".to_string(), None, 1, ok), Err(TeamError::GivenCell(0)));
+        assert_eq!(grid.set(4, content(2, "alice"), "alice".to_string(), None, 1, ok), Err(TeamError::InvalidPosition(4)));
+        assert_eq!(grid.set(1, content(5, "alice"), "alice".to_string(), None, 1, ok), Err(TeamError::InvalidValue(5)));
+
+        let op = grid.set(1, content(2, "alice"), "alice".to_string(), None, 1, ok).unwrap();
+        assert_eq!((op.kind, op.version, op.previous), (OperationKind::Set, 1, None));
+        grid.set(2, content(3, "bob"), "bob".to_string(), None, 2, ok).unwrap();
+        assert_eq!(grid.values(), vec![1, 2, 3, 0]);
+        assert_eq!(grid.filled().unwrap(), vec![(1, content(2, "alice")), (2, content(3, "bob"))]);
+
+        // bob overwrites a value of alice he had not seen
+        grid.set(1, content(4, "bob"), "bob".to_string(), Some(0), 3, ok).unwrap();
+        assert_eq!(grid.conflicts().len(), 1);
+        assert_eq!(grid.conflicts()[0].overwritten, Some(content(2, "alice")));
+        // alice saw the last version
+        let version = grid.version(1).unwrap();
+        grid.remove(1, "alice".to_string(), Some(version), 4, ok).unwrap();
+        assert_eq!(grid.conflicts().len(), 1);
+        assert_eq!(grid.cell(1).unwrap(), None);
+
+        // the check of the game runs before anything is written
+        let refused = grid.set(3, content(1, "alice"), "alice".to_string(), None, 5, |_| Err(TeamError::NothingTo("claim")));
+        assert!(refused.is_err());
+        assert_eq!(grid.cell(3).unwrap(), None);
+        assert_eq!(grid.history(0, 10).len(), 4);
+    }
+
+    #[test]
+    fn test_undo_redo_replay() {
+        let mut grid = TeamGrid::<Tiny>::default();
+        grid.set(0, content(1, "alice"), "alice".to_string(), None, 1, ok).unwrap();
+        grid.set(0, content(2, "alice"), "alice".to_string(), None, 2, ok).unwrap();
+        grid.set(1, content(3, "bob"), "bob".to_string(), None, 3, ok).unwrap();
+
+        let undo = grid.undo("alice".to_string(), 4, ok).unwrap();
+        assert_eq!(undo.kind, OperationKind::Undo(1));
+        assert_eq!(grid.cell(0).unwrap(), Some(content(1, "alice")));
+        grid.undo("alice".to_string(), 5, ok).unwrap();
+        assert_eq!(grid.cell(0).unwrap(), None);
+        assert_eq!(grid.undo("alice".to_string(), 6, ok), Err(TeamError::NothingTo("undo")));
+
+        grid.redo("alice".to_string(), 6, ok).unwrap();
+        assert_eq!(grid.cell(0).unwrap(), Some(content(1, "alice")));
+        // bob changed the cell, alice cannot redo over it
+        grid.set(0, content(4, "bob"), "bob".to_string(), None, 7, ok).unwrap();
+        assert!(matches!(grid.redo("alice".to_string(), 8, ok), Err(TeamError::CellChanged { .. })));
+        // nor undo bob's change
+        assert_eq!(grid.undo("carol".to_string(), 8, ok), Err(TeamError::NothingTo("undo")));
+
+        // back to right after the first write
+        let replay = grid.replay_to(0, "carol".to_string(), 9, ok).unwrap();
+        assert_eq!(replay.len(), 2);
+        assert_eq!(grid.values(), vec![1, 0, 0, 0]);
+        assert_eq!(grid.replay_to(100, "carol".to_string(), 9, ok), Err(TeamError::UnknownOperation(100)));
+
+        // a change is undone by the identity which made it, whatever the address of its content
+        grid.set(2, content(3, "dave"), "dave-phone".to_string(), None, 10, ok).unwrap();
+        assert_eq!(grid.undo("dave".to_string(), 11, ok), Err(TeamError::NothingTo("undo")));
+        grid.undo("dave-phone".to_string(), 11, ok).unwrap();
+        assert_eq!(grid.cell(2).unwrap(), None);
+    }
+
+    #[test]
+    fn test_givens_set_after_the_history() {
+        let mut grid = TeamGrid::<Tiny>::default();
+        grid.set(0, content(2, "alice"), "alice".to_string(), None, 1, ok).unwrap();
+        grid.remove(0, "alice".to_string(), None, 2, ok).unwrap();
+        grid.set(1, content(3, "bob"), "bob".to_string(), None, 3, ok).unwrap();
+        grid.undo("bob".to_string(), 4, ok).unwrap();
+        grid.set_givens(vec![(0, 1), (1, 4)], "server".to_string(), 5).unwrap();
+
+        // none of them can write over a given
+        assert_eq!(grid.undo("alice".to_string(), 5, ok), Err(TeamError::GivenCell(0)));
+        assert_eq!(grid.redo("bob".to_string(), 5, ok), Err(TeamError::GivenCell(1)));
+        assert_eq!(grid.replay_to(0, "carol".to_string(), 5, ok), Err(TeamError::GivenCell(0)));
+        assert_eq!(grid.values(), vec![1, 4, 0, 0]);
+        assert_eq!(grid.history(0, 10).len(), 4);
+    }
+
+    #[test]
+    fn test_givens_over_filled_cells() {
+        let mut grid = TeamGrid::<Tiny>::default();
+        grid.set(0, content(2, "alice"), "alice".to_string(), None, 1, ok).unwrap();
+        grid.set(2, content(3, "bob"), "bob".to_string(), None, 2, ok).unwrap();
+        assert_eq!(grid.set_givens(vec![(0, 1), (1, 5)], "server".to_string(), 3), Err(TeamError::InvalidValue(5)));
+
+        // the givens win over the cells of the members
+        let removals = grid.set_givens(vec![(0, 1), (1, 4)], "server".to_string(), 3).unwrap();
+        assert_eq!(removals.len(), 1);
+        assert_eq!(
+            (removals[0].kind, removals[0].position, removals[0].previous.clone(), removals[0].editor.as_str()),
+            (OperationKind::Remove, 0, Some(content(2, "alice")), "server")
+        );
+        assert_eq!(grid.values(), vec![1, 4, 3, 0]);
+        assert_eq!(grid.filled().unwrap(), vec![(2, content(3, "bob"))]);
+        assert!(grid.undo("alice".to_string(), 4, ok).is_err());
+        assert_eq!(grid.values(), vec![1, 4, 3, 0]);
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut grid = TeamGrid::<Tiny>::default();
+        grid.set(0, content(1, "alice"), "alice".to_string(), None, 1, ok).unwrap();
+        assert!(!grid.restore(0, content(2, "bob")).unwrap());
+        assert!(grid.restore(1, content(2, "bob")).unwrap());
+        assert_eq!(grid.values(), vec![1, 2, 0, 0]);
+        assert_eq!(grid.history(0, 10).len(), 1);
+    }
+}
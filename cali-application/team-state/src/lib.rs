@@ -0,0 +1,11 @@
+//! Team state shared by the Calimero applications of the games: a board edited by the members
+//! of a private context, with the history of its changes and the votes deciding how the team
+//! submits it. Each game application wraps it in its `#[app::state]` and adds its own rules.
+
+pub mod error;
+pub mod grid;
+pub mod voting;
+
+pub use error::TeamError;
+pub use grid::{Board, CellConflict, CellContent, Operation, OperationKind, TeamGrid};
+pub use voting::{Electorate, Proposal, ProposalStatus, Quorum, Voting, VotingSettings};
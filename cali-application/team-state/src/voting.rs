@@ -0,0 +1,256 @@
+use crate::error::TeamError;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+/// How many members must approve a proposal before the team submits it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub enum Quorum {
+    /// More than half of the team
+    #[default]
+    Majority,
+    Unanimous,
+    /// The captain alone decides
+    CaptainOnly,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct VotingSettings {
+    pub quorum: Quorum,
+}
+
+/// The members allowed to vote, read from the roles of the team when a vote is cast so that
+/// the quorum follows the team as members join or change roles.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Electorate {
+    pub voters: Vec<String>,
+    /// Deciding alone with [`Quorum::CaptainOnly`]
+    pub captain: Option<String>,
+}
+
+/// A proposal to submit the board as it was when it was made.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, Serialize)]
+pub struct Proposal {
+    pub id: usize,
+    pub proposer: String,
+    pub public: bool,
+    /// Hash of the board proposed, see [`crate::TeamGrid::hash`]
+    pub grid_hash: String,
+    /// (member, approves)
+    pub votes: Vec<(String, bool)>,
+    pub status: ProposalStatus,
+    /// Milliseconds since the epoch
+    pub created_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize, Serialize)]
+pub enum ProposalStatus {
+    Open,
+    /// Reached the quorum, the board can be submitted
+    Finalized,
+    Rejected,
+    /// Replaced by a newer proposal
+    Superseded,
+}
+
+/// The proposals of a team to submit its board, and the rules deciding them.
+///
+/// Calls take the hash of the current board: a proposal can only be voted on while the board is
+/// the one it proposes.
+#[derive(Default, BorshSerialize, BorshDeserialize)]
+pub struct Voting {
+    settings: VotingSettings,
+    /// proposals[id], latest last
+    proposals: Vec<Proposal>,
+}
+
+impl Voting {
+    pub fn configure(&mut self, settings: VotingSettings) {
+        self.settings = settings;
+    }
+
+    pub fn settings(&self) -> &VotingSettings {
+        &self.settings
+    }
+
+    /// Proposes to submit the board, publicly or with a proof. The proposer approves it and the
+    /// open proposals are superseded.
+    pub fn propose(
+        &mut self,
+        public: bool,
+        proposer: String,
+        grid_hash: String,
+        now: u64,
+        electorate: &Electorate,
+    ) -> Result<Proposal, TeamError> {
+        for proposal in self.proposals.iter_mut().filter(|p| p.status == ProposalStatus::Open) {
+            proposal.status = ProposalStatus::Superseded;
+        }
+        let id = self.proposals.len();
+        self.proposals.push(Proposal {
+            id,
+            proposer: proposer.clone(),
+            public,
+            grid_hash: grid_hash.clone(),
+            votes: vec![],
+            status: ProposalStatus::Open,
+            created_at: now,
+        });
+        self.vote(id, proposer, true, &grid_hash, electorate)
+    }
+
+    /// Approves or rejects a proposal, replacing any earlier vote of `voter` on it. Only the
+    /// votes of the current `electorate` count.
+    pub fn vote(
+        &mut self,
+        id: usize,
+        voter: String,
+        approve: bool,
+        grid_hash: &str,
+        electorate: &Electorate,
+    ) -> Result<Proposal, TeamError> {
+        let settings = &self.settings;
+        let proposal = Self::open(&mut self.proposals, id, grid_hash)?;
+        proposal.votes.retain(|(member, _)| *member != voter);
+        proposal.votes.push((voter, approve));
+        proposal.status = settings.tally(&proposal.votes, electorate);
+        Ok(proposal.clone())
+    }
+
+    pub fn withdraw(&mut self, id: usize, voter: &str, grid_hash: &str) -> Result<Proposal, TeamError> {
+        let proposal = Self::open(&mut self.proposals, id, grid_hash)?;
+        proposal.votes.retain(|(member, _)| member != voter);
+        Ok(proposal.clone())
+    }
+
+    /// `limit` proposals starting at `from`.
+    pub fn proposals(&self, from: usize, limit: usize) -> Vec<Proposal> {
+        self.proposals.iter().skip(from).take(limit).cloned().collect()
+    }
+
+    /// The latest proposal which reached the quorum, if the board did not change since.
+    pub fn finalized(&self, grid_hash: &str) -> Option<&Proposal> {
+        self.proposals
+            .iter()
+            .rev()
+            .find(|p| p.status == ProposalStatus::Finalized)
+            .filter(|p| p.grid_hash == grid_hash)
+    }
+
+    /// The proposal, unless it is closed or the board changed since it was made.
+    fn open<'a>(proposals: &'a mut [Proposal], id: usize, grid_hash: &str) -> Result<&'a mut Proposal, TeamError> {
+        let proposal = proposals.get_mut(id).ok_or(TeamError::UnknownProposal(id))?;
+        if proposal.status != ProposalStatus::Open {
+            return Err(TeamError::ProposalClosed(id));
+        }
+        if proposal.grid_hash != grid_hash {
+            return Err(TeamError::ProposalOutdated(id));
+        }
+        Ok(proposal)
+    }
+}
+
+impl VotingSettings {
+    fn tally(&self, votes: &[(String, bool)], electorate: &Electorate) -> ProposalStatus {
+        let votes: Vec<_> = votes.iter().filter(|(member, _)| electorate.voters.contains(member)).collect();
+        let team_size = electorate.voters.len() as u32;
+        let approvals = votes.iter().filter(|(_, approve)| *approve).count() as u32;
+        let rejections = votes.len() as u32 - approvals;
+        let decided = match self.quorum {
+            Quorum::Majority if approvals * 2 > team_size => Some(true),
+            Quorum::Majority if rejections * 2 >= team_size => Some(false),
+            Quorum::Unanimous if rejections > 0 => Some(false),
+            Quorum::Unanimous if approvals >= team_size => Some(true),
+            Quorum::CaptainOnly => votes
+                .iter()
+                .find(|(member, _)| Some(member) == electorate.captain.as_ref())
+                .map(|(_, approve)| *approve),
+            _ => None,
+        };
+        match decided {
+            Some(true) => ProposalStatus::Finalized,
+            Some(false) => ProposalStatus::Rejected,
+            None => ProposalStatus::Open,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::TeamError;
+    use crate::voting::{Electorate, ProposalStatus, Quorum, Voting, VotingSettings};
+
+    fn voting(quorum: Quorum) -> Voting {
+        let mut voting = Voting::default();
+        voting.configure(VotingSettings { quorum });
+        voting
+    }
+
+    fn electorate(voters: &[&str], captain: Option<&str>) -> Electorate {
+        Electorate {
+            voters: voters.iter().map(|voter| voter.to_string()).collect(),
+            captain: captain.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_majority() {
+        let team = electorate(&["alice", "bob", "carol", "dave"], None);
+        let mut voting = voting(Quorum::Majority);
+        let proposal = voting.propose(true, "alice".to_string(), "h1".to_string(), 1, &team).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Open);
+
+        voting.vote(0, "bob".to_string(), true, "h1", &team).unwrap();
+        // bob changes his mind, his vote is replaced
+        let proposal = voting.vote(0, "bob".to_string(), false, "h1", &team).unwrap();
+        assert_eq!(proposal.votes.len(), 2);
+        assert_eq!(proposal.status, ProposalStatus::Open);
+
+        voting.withdraw(0, "bob", "h1").unwrap();
+        voting.vote(0, "bob".to_string(), true, "h1", &team).unwrap();
+        // eve is not a solver, her vote does not count
+        let proposal = voting.vote(0, "eve".to_string(), true, "h1", &team).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Open);
+        assert_eq!(voting.finalized("h1"), None);
+        let proposal = voting.vote(0, "carol".to_string(), true, "h1", &team).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Finalized);
+        assert_eq!(voting.finalized("h1").map(|p| p.id), Some(0));
+        // the board changed since
+        assert_eq!(voting.finalized("h2"), None);
+        assert_eq!(voting.vote(0, "dave".to_string(), true, "h1", &team), Err(TeamError::ProposalClosed(0)));
+    }
+
+    #[test]
+    fn test_team_size_follows_the_roles() {
+        let mut voting = voting(Quorum::Majority);
+        let team = electorate(&["alice", "bob", "carol", "dave"], None);
+        voting.propose(true, "alice".to_string(), "h1".to_string(), 1, &team).unwrap();
+        // dave and carol became spectators, alice and bob are a majority of the two solvers left
+        let proposal = voting.vote(0, "bob".to_string(), true, "h1", &electorate(&["alice", "bob"], None)).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Finalized);
+    }
+
+    #[test]
+    fn test_outdated_and_superseded() {
+        let team = electorate(&["alice", "bob"], None);
+        let mut voting = voting(Quorum::Unanimous);
+        voting.propose(false, "alice".to_string(), "h1".to_string(), 1, &team).unwrap();
+        assert_eq!(voting.vote(0, "bob".to_string(), true, "h2", &team), Err(TeamError::ProposalOutdated(0)));
+        assert_eq!(voting.vote(1, "bob".to_string(), true, "h1", &team), Err(TeamError::UnknownProposal(1)));
+
+        voting.propose(true, "bob".to_string(), "h2".to_string(), 2, &team).unwrap();
+        let proposals = voting.proposals(0, 10);
+        assert_eq!(proposals[0].status, ProposalStatus::Superseded);
+        let proposal = voting.vote(1, "alice".to_string(), false, "h2", &team).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Rejected);
+    }
+
+    #[test]
+    fn test_captain_only() {
+        let team = electorate(&["alice", "bob"], Some("alice"));
+        let mut voting = voting(Quorum::CaptainOnly);
+        let proposal = voting.propose(true, "bob".to_string(), "h1".to_string(), 1, &team).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Open);
+        let proposal = voting.vote(0, "alice".to_string(), true, "h1", &team).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Finalized);
+    }
+}
@@ -1,12 +1,13 @@
 use std::sync::Arc;
 use crate::error::ContractError;
 use crate::error::ContractError::{InvalidAction, InvalidSolution};
-use crate::game::{GameSolution, PlayerContribution, SudokuGame};
-use crate::logic::check_solution;
+use crate::game::{GameSolution, HintedPlayer, PlayerContribution, SudokuGame, TeamHints};
+use crate::logic::{check_solution, hint_payout};
 use crate::state::{GlobalState, GAME_STORAGE, OWNER, OWNER_PROFIT, VERIFIER, VK};
 use candid::Principal;
 use ic_cdk::{api, call};
 use ic_ledger_types::{AccountIdentifier, Memo, Tokens, DEFAULT_SUBACCOUNT, MAINNET_LEDGER_CANISTER_ID};
+use verifier::public_values::decode_sudoku_initial_state;
 
 #[cfg_attr(not(feature = "library"), ic_cdk::init)]
 fn instantiate(vk: String, verifier_address: Principal) {
@@ -41,6 +42,8 @@ fn create_new_battle(
     deposit_price: u128,
     service_fee: u128,
     creator: Principal,
+    hint_budget: u32,
+    hint_penalty: f32,
 ) -> Result<usize, ContractError> {
     OWNER.with_borrow(|owner| {
         if *owner != api::caller() {
@@ -48,6 +51,9 @@ fn create_new_battle(
         }
         Ok(())
     })?;
+    if !(0.0..=1.0).contains(&hint_penalty) {
+        return Err(InvalidAction("hint penalty must be between 0 and 1".to_string()));
+    }
 
     GAME_STORAGE.with_borrow_mut(|game| {
         let new_battle_id = game.len();
@@ -60,6 +66,11 @@ fn create_new_battle(
             solution: None,
             winners: None,
             claimed: false,
+            verification_receipt: None,
+            hint_budget,
+            hint_penalty,
+            hints: vec![],
+            winning_team: None,
         });
         Ok(new_battle_id)
     })
@@ -119,8 +130,89 @@ fn start_game(battle_id: usize, initial_state: Vec<(u8, u8)>) -> Result<(), Cont
     })
 }
 
+/// Records a hint given to `team` with its current `players`, returns the hints it has left.
 #[cfg_attr(not(feature = "library"), ic_cdk::update)]
-async fn submit_solution(battle_id: usize, solution: GameSolution, player_contributions: Vec<PlayerContribution>) -> Result<(), ContractError> {
+fn use_hint(battle_id: usize, team: String, mut players: Vec<Principal>) -> Result<u32, ContractError> {
+    OWNER.with_borrow(|owner| {
+        if *owner != api::caller() {
+            return Err(InvalidAction("only owner can give hints".to_string()));
+        }
+        Ok(())
+    })?;
+
+    GAME_STORAGE.with_borrow_mut(|games| {
+        let game = games
+            .get_mut(battle_id)
+            .ok_or(InvalidAction("battle not found".to_string()))?;
+        if game.winners.is_some() {
+            return Err(InvalidAction("the game is overed".to_string()));
+        }
+        if game.initial_state.is_none() {
+            return Err(InvalidAction("initial game state is none".to_string()));
+        }
+        let used = game.hints_used(&team);
+        if used >= game.hint_budget {
+            return Err(InvalidAction(format!("team {} has no hint left", team)));
+        }
+        let index = match game.hints.iter().position(|hints| hints.team == team) {
+            Some(index) => index,
+            None => {
+                game.hints.push(TeamHints { team, used: 0, players: vec![] });
+                game.hints.len() - 1
+            }
+        };
+        let hints = &mut game.hints[index];
+        hints.used += 1;
+        players.sort();
+        players.dedup();
+        for player in players {
+            match hints.players.iter_mut().find(|hinted| hinted.player == player) {
+                Some(hinted) => hinted.hints += 1,
+                None => hints.players.push(HintedPlayer { player, hints: 1 }),
+            }
+        }
+        Ok(game.hint_budget - used - 1)
+    })
+}
+
+/// Gives back a hint `team` was charged for but never got, with the `players` it was charged
+/// to. Returns the hints the team has left.
+#[cfg_attr(not(feature = "library"), ic_cdk::update)]
+fn refund_hint(battle_id: usize, team: String, mut players: Vec<Principal>) -> Result<u32, ContractError> {
+    OWNER.with_borrow(|owner| {
+        if *owner != api::caller() {
+            return Err(InvalidAction("only owner can refund hints".to_string()));
+        }
+        Ok(())
+    })?;
+
+    GAME_STORAGE.with_borrow_mut(|games| {
+        let game = games
+            .get_mut(battle_id)
+            .ok_or(InvalidAction("battle not found".to_string()))?;
+        if game.winners.is_some() {
+            return Err(InvalidAction("the game is overed".to_string()));
+        }
+        let hints = game
+            .hints
+            .iter_mut()
+            .find(|hints| hints.team == team && hints.used > 0)
+            .ok_or(InvalidAction(format!("team {} took no hint", team)))?;
+        hints.used -= 1;
+        players.sort();
+        players.dedup();
+        for hinted in hints.players.iter_mut().filter(|hinted| players.contains(&hinted.player)) {
+            hinted.hints = hinted.hints.saturating_sub(1);
+        }
+        hints.players.retain(|hinted| hinted.hints > 0);
+        Ok(game.hint_budget - hints.used)
+    })
+}
+
+/// Pays the prize pool to the contributors of a solution. The pool shrinks with each hint taken
+/// by `team` or by a team of the contributors, the rest goes to the owner.
+#[cfg_attr(not(feature = "library"), ic_cdk::update)]
+async fn submit_solution(battle_id: usize, solution: GameSolution, player_contributions: Vec<PlayerContribution>, team: Option<String>) -> Result<(), ContractError> {
     OWNER.with_borrow(|owner| {
         if *owner != api::caller() {
             return Err(InvalidAction("only owner can submit solution".to_string()));
@@ -131,7 +223,7 @@ async fn submit_solution(battle_id: usize, solution: GameSolution, player_contri
     let verifier = VERIFIER.with_borrow(|v| *v);
     let player_contributions_clone = Arc::new(&player_contributions);
     
-    let (initial_state, number_of_player, deposit_price) = GAME_STORAGE.with_borrow_mut(|games| {
+    let (initial_state, number_of_player, deposit_price, hints, hint_penalty) = GAME_STORAGE.with_borrow_mut(|games| {
         let game = games
             .get_mut(battle_id)
             .ok_or(InvalidAction("battle not found".to_string()))?;
@@ -150,26 +242,37 @@ async fn submit_solution(battle_id: usize, solution: GameSolution, player_contri
         if game.initial_state.is_none() {
             return Err(InvalidAction("initial game state is none".to_string()));
         }
-        Ok((game.initial_state.clone().unwrap(), game.players.len(), game.deposit_price))
+        let hints = game.hints_charged(team.as_deref(), &player_contributions_clone);
+        Ok((game.initial_state.clone().unwrap(), game.players.len(), game.deposit_price, hints, game.hint_penalty))
     })?;
 
-    match &solution {
+    let verification_receipt = match &solution {
         GameSolution::Public(solution) => {
             check_solution(&initial_state, solution)?;
+            None
         }
         GameSolution::Private(proof) => {
-            let (g,): (Result<(), verifier::error::ContractError>,) = call(
+            let public_values = hex::decode(&proof.public_values)
+                .map_err(|_| InvalidSolution("public values are not hex encoded".to_string()))?;
+            let proven_initial_state = decode_sudoku_initial_state(&public_values)
+                .map_err(|e| InvalidSolution(e.to_string()))?;
+            if proven_initial_state != initial_state {
+                return Err(InvalidSolution("proof is for a different initial state".to_string()));
+            }
+
+            let (receipt_id,): (Result<u64, verifier::error::ContractError>,) = call(
                 verifier,
                 "verify_proof",
                 (proof, VK.with(|vk| vk.borrow().clone())),
             )
             .await
             .map_err(|e| InvalidSolution(e.1))?;
-            g.map_err(|e| InvalidSolution(e.to_string()))?;
+            Some(receipt_id.map_err(|e| InvalidSolution(e.to_string()))?)
         }
     };
 
-    let total_prize_pool = (number_of_player as u64) * (deposit_price as u64);
+    let full_prize_pool = (number_of_player as u64) * (deposit_price as u64);
+    let total_prize_pool = hint_payout(full_prize_pool, hints, hint_penalty);
     let mut transferred_token = 0u64;
     for (i, player) in player_contributions.iter().enumerate() {
         let amount = if i == player_contributions.len() - 1 {
@@ -203,6 +306,11 @@ async fn submit_solution(battle_id: usize, solution: GameSolution, player_contri
         
         game.solution = Some(solution);
         game.winners = Some(player_contributions);
+        game.verification_receipt = verification_receipt;
+        game.winning_team = team;
+    });
+    OWNER_PROFIT.with_borrow_mut(|profit| {
+        *profit += full_prize_pool - total_prize_pool;
     });
     
     Ok(())
@@ -260,7 +368,7 @@ mod tests {
                 sudoku_id,
                 owner,
                 "create_new_battle",
-                encode_args((1u128, 1u128, player1)).unwrap(),
+                encode_args((1u128, 1u128, player1, 3u32, 0.1f32)).unwrap(),
             )
             .unwrap();
 
@@ -306,6 +414,57 @@ mod tests {
             }
         }
 
+        match pic
+            .update_call(
+                sudoku_id,
+                owner,
+                "use_hint",
+                encode_args((battle_id, "team".to_string(), vec![player1])).unwrap(),
+            )
+            .unwrap()
+        {
+            WasmResult::Reply(x) => {
+                assert_eq!(Decode!(&x, Result<u32, ContractError>).unwrap().unwrap(), 2);
+            }
+            WasmResult::Reject(e) => {
+                panic!("{}", e);
+            }
+        }
+
+        match pic
+            .update_call(
+                sudoku_id,
+                owner,
+                "refund_hint",
+                encode_args((battle_id, "team".to_string(), vec![player1])).unwrap(),
+            )
+            .unwrap()
+        {
+            WasmResult::Reply(x) => {
+                assert_eq!(Decode!(&x, Result<u32, ContractError>).unwrap().unwrap(), 3);
+            }
+            WasmResult::Reject(e) => {
+                panic!("{}", e);
+            }
+        }
+
+        match pic
+            .update_call(
+                sudoku_id,
+                owner,
+                "use_hint",
+                encode_args((battle_id, "team".to_string(), vec![player1])).unwrap(),
+            )
+            .unwrap()
+        {
+            WasmResult::Reply(x) => {
+                assert_eq!(Decode!(&x, Result<u32, ContractError>).unwrap().unwrap(), 2);
+            }
+            WasmResult::Reject(e) => {
+                panic!("{}", e);
+            }
+        }
+
         match pic
             .update_call(
                 sudoku_id,
@@ -314,7 +473,7 @@ mod tests {
                 encode_args((battle_id, GameSolution::Private(sp1_proof()), vec![PlayerContribution {
                     player: player1,
                     percent: 1.0,
-                }])).unwrap(),
+                }], None::<String>)).unwrap(),
             )
             .unwrap()
         {
@@ -3,25 +3,83 @@ use serde::{Deserialize, Serialize};
 use verifier::types::SP1ProofWithPublicValues;
 
 #[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct SudokuGame {
+    /// `[cell, value]` pairs of the given cells
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Vec<Vec<u8>>>))]
     pub initial_state: Option<Vec<(u8, u8)>>,
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
     pub creator: Principal,
     pub deposit_price: u128,
     pub service_fee: u128,
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<String>))]
     pub players: Vec<Principal>,
     pub solution: Option<GameSolution>,
     pub winners: Option<Vec<PlayerContribution>>,
-    pub claimed: bool
+    pub claimed: bool,
+    /// Verifier canister receipt of the winning proof, for private solutions
+    pub verification_receipt: Option<u64>,
+    /// Hints each team can take during the battle
+    pub hint_budget: u32,
+    /// Share of the prize pool a team loses for each hint it took, between 0 and 1
+    pub hint_penalty: f32,
+    /// Hints taken by the teams which asked for some
+    pub hints: Vec<TeamHints>,
+    /// Team whose solution was accepted, when it was submitted as a team
+    pub winning_team: Option<String>,
+}
+
+impl SudokuGame {
+    /// Hints taken by `team`.
+    pub fn hints_used(&self, team: &str) -> u32 {
+        self.hints.iter().find(|hints| hints.team == team).map_or(0, |hints| hints.used)
+    }
+
+    /// Hints charged to a solution: those of `team` and of every team one of the contributors
+    /// played in, so leaving the team out doesn't skip the penalty.
+    pub fn hints_charged(&self, team: Option<&str>, contributions: &[PlayerContribution]) -> u32 {
+        self.hints
+            .iter()
+            .filter(|hints| {
+                team == Some(hints.team.as_str())
+                    || contributions.iter().any(|c| hints.players.iter().any(|p| p.player == c.player))
+            })
+            .map(|hints| hints.used)
+            .sum()
+    }
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TeamHints {
+    /// Calimero context of the team
+    pub team: String,
+    pub used: u32,
+    /// Players of the team when it took its hints
+    pub players: Vec<HintedPlayer>,
+}
+
+/// A player charged for the hints of a team.
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HintedPlayer {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub player: Principal,
+    /// Hints taken while the player was in the team, a refund gives them back
+    pub hints: u32,
 }
 
 #[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum GameSolution {
     Public(Vec<u8>),
     Private(SP1ProofWithPublicValues)
 }
 
 #[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct PlayerContribution {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
     pub player: Principal,
     pub percent: f32,
 }
\ No newline at end of file
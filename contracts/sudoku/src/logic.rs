@@ -71,6 +71,13 @@ pub(crate) fn check_solution(initial_state: &[(u8, u8)], answer: &[u8]) -> Resul
     Ok(())
 }
 
+/// Part of `prize_pool` paid to a team which took `hints` hints, each costing `hint_penalty` of
+/// the pool.
+pub(crate) fn hint_payout(prize_pool: u64, hints: u32, hint_penalty: f32) -> u64 {
+    let kept = (1.0 - hints as f64 * hint_penalty as f64).max(0.0);
+    ((prize_pool as f64) * kept) as u64
+}
+
 fn is_valid_group(group: &[u8; 9]) -> bool {
     let mut seen = [false; 9];
     for x in group {
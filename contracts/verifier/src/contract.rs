@@ -1,133 +1,229 @@
 use crate::error::ContractError;
-use crate::error::ContractError::InvalidProof;
-use crate::groth16::verify_groth16_proof;
-use crate::types::{ProofConsts, SP1Proof, SP1ProofWithPublicValues};
-use crate::utils::hash_public_values;
+use crate::groth16::{
+    parse_public_inputs, proof_consts_from_verifying_key, proof_from_groth16_proof,
+    verify_groth16_proof,
+};
+use crate::public_values::{decode_with_schema, PublicValuesSchema};
+use crate::sp1::verify_sp1_groth16;
+use crate::state::{StableState, RECEIPTS, RECEIPT_TREE, VERIFIED_PROOFS, VERIFYING_KEYS};
+use crate::types::{
+    CertifiedReceipt, DecodedPublicValues, Groth16Proof, Groth16VerifyingKey, SP1Proof,
+    SP1ProofWithPublicValues, VerificationReceipt,
+};
+use crate::utils::{proof_digest, receipt_hash, receipt_key, RECEIPTS_LABEL};
+use candid::Principal;
+use ic_cdk::api;
+use ic_certified_map::{labeled, labeled_hash, AsHashTree};
+use serde::Serialize;
 use num_bigint::BigUint;
-use num_traits::FromBytes;
-use std::cell::RefCell;
-use std::str::FromStr;
-
-thread_local! {
-    static PROOF_CONSTANTS: RefCell<ProofConsts> = RefCell::new(ProofConsts {
-            r: BigUint::from_str("21888242871839275222246405745257275088548364400416034343698204186575808495617").unwrap(),
-            q: BigUint::from_str("21888242871839275222246405745257275088696311157297823662689037894645226208583").unwrap(),
-            alpha_x: BigUint::from_str(
-                "20491192805390485299153009773594534940189261866228447918068658471970481763042",
-            ).unwrap(),
-            alpha_y: BigUint::from_str(
-                "9383485363053290200918347156157836566562967994039712273449902621266178545958",
-            ).unwrap(),
-            beta_x_0: BigUint::from_str(
-                "4252822878758300859123897981450591353533073413197771768651442665752259397132",
-            ).unwrap(),
-            beta_x_1: BigUint::from_str(
-                "6375614351688725206403948262868962793625744043794305715222011528459656738731",
-            ).unwrap(),
-            beta_y_0: BigUint::from_str(
-                "41207766310529818958173054109690360505148424997958324311878202295167071904",
-            ).unwrap(),
-            beta_y_1: BigUint::from_str(
-                "11383000245469012944693504663162918391286475477077232690815866754273895001727",
-            ).unwrap(),
-            gamma_x_0: BigUint::from_str(
-                "11559732032986387107991004021392285783925812861821192530917403151452391805634",
-            ).unwrap(),
-            gamma_x_1: BigUint::from_str(
-                "10857046999023057135944570762232829481370756359578518086990519993285655852781",
-            ).unwrap(),
-            gamma_y_0: BigUint::from_str(
-                "17805874995975841540914202342111839520379459829704422454583296818431106115052",
-            ).unwrap(),
-            gamma_y_1: BigUint::from_str(
-                "13392588948715843804641432497768002650278120570034223513918757245338268106653",
-            ).unwrap(),
-            delta_x_0: BigUint::from_str(
-                "15228921016905275799965683851057599185732146900672862119304638397355443707454",
-            ).unwrap(),
-            delta_x_1: BigUint::from_str(
-                "20409334339251888712441068872870136503388893688417321778880931483231354129143",
-            ).unwrap(),
-            delta_y_0: BigUint::from_str(
-                "7264328423023871043957062501982202910010974353266020262517510882033855725085",
-            ).unwrap(),
-            delta_y_1: BigUint::from_str(
-                "19877240297427940090516929163964707799250026674662843458659550601453241207282",
-            ).unwrap(),
-            ic_x: [
-                BigUint::from_str(
-                    "1048662465858378462039546739271490393047466817500184066040897033812283855432",
-                ).unwrap(),
-                BigUint::from_str(
-                    "8880870171820178879315138727491177243876706074048591778960954962478121292427",
-                ).unwrap(),
-                BigUint::from_str(
-                    "1568022209015269256635037559407721536321962002203490351642786232392950111552",
-                ).unwrap(),
-            ],
-            ic_y: [
-                BigUint::from_str(
-                    "19955603142782979624537809602226324406438922060777785976295407357266173213699",
-                ).unwrap(),
-                BigUint::from_str(
-                    "9645782069397560173565155195986190258176410930403315934875366540532269958807",
-                ).unwrap(),
-                BigUint::from_str(
-                    "7099579151192302668913697450512320120875549565559727060892675623506103788981",
-                ).unwrap(),
-            ],
-        });
+use sha2::{Digest, Sha256};
+
+/// Saves the receipts and the verifying keys to stable memory, which survives the upgrade.
+#[cfg_attr(not(feature = "library"), ic_cdk::pre_upgrade)]
+fn pre_upgrade() {
+    let state = bincode::serialize(&StableState::save()).expect("failed to encode the verifier state");
+    ic_cdk::storage::stable_save((state,)).expect("failed to save the verifier state");
 }
 
-#[cfg_attr(not(feature = "library"), ic_cdk::query)]
-pub fn verify_proof(proof: SP1ProofWithPublicValues, program_vkey: String) -> Result<(), ContractError> {
+/// Restores the state saved by `pre_upgrade` and certifies its receipts again, so receipt ids
+/// are never reused. A canister upgraded from a build which saved nothing starts empty.
+#[cfg_attr(not(feature = "library"), ic_cdk::post_upgrade)]
+fn post_upgrade() {
+    if api::stable::stable_size() > 0 {
+        let (state,): (Vec<u8>,) = ic_cdk::storage::stable_restore().expect("failed to read the verifier state");
+        bincode::deserialize::<StableState>(&state)
+            .expect("failed to decode the verifier state")
+            .restore();
+    }
+    certify_receipts();
+}
+
+/// Returns the receipt of an already verified proof, or runs `verify` and records a new receipt.
+fn verify_with_receipt(
+    vk_id: String,
+    proof: &[u8],
+    public_values: &[u8],
+    caller: Principal,
+    timestamp: u64,
+    verify: impl FnOnce() -> Result<(), ContractError>,
+) -> Result<u64, ContractError> {
+    let digest = hex::encode(proof_digest(&vk_id, proof, public_values));
+    if let Some(id) = VERIFIED_PROOFS.with_borrow(|proofs| proofs.get(&digest).copied()) {
+        return Ok(id);
+    }
+
+    verify()?;
+
+    let receipt = RECEIPTS.with_borrow_mut(|receipts| {
+        let receipt = VerificationReceipt {
+            id: receipts.len() as u64,
+            proof_digest: digest.clone(),
+            vk_id,
+            public_values_hash: hex::encode(Sha256::digest(public_values)),
+            timestamp,
+            caller,
+        };
+        receipts.push(receipt.clone());
+        receipt
+    });
+    VERIFIED_PROOFS.with_borrow_mut(|proofs| proofs.insert(digest, receipt.id));
+    RECEIPT_TREE.with_borrow_mut(|tree| tree.insert(receipt_key(receipt.id), receipt_hash(&receipt)));
+    Ok(receipt.id)
+}
+
+/// Sets the canister's certified data to the root hash of the receipts tree.
+fn certify_receipts() {
+    let root_hash = RECEIPT_TREE.with_borrow(|tree| labeled_hash(RECEIPTS_LABEL, &tree.root_hash()));
+    api::set_certified_data(&root_hash);
+}
+
+/// CBOR encoded witness of `receipts/<receipt id>` in the certified data tree.
+fn receipt_witness(receipt_id: u64) -> Vec<u8> {
+    RECEIPT_TREE.with_borrow(|tree| {
+        let witness = labeled(RECEIPTS_LABEL, tree.witness(&receipt_key(receipt_id)));
+        let mut serializer = serde_cbor::Serializer::new(vec![]);
+        serializer.self_describe().unwrap();
+        witness.serialize(&mut serializer).unwrap();
+        serializer.into_inner()
+    })
+}
+
+fn verify_sp1_proof(
+    proof: SP1ProofWithPublicValues,
+    program_vkey: String,
+    caller: Principal,
+    timestamp: u64,
+) -> Result<u64, ContractError> {
     match proof.proof {
         SP1Proof::Groth16(proof_bytes) => {
-            let verifier_hash = hex::decode("090690902a12d1d02c07a1ad25aa76bded5f6499e12a11ba127669501b553998").unwrap();
             let proof_bytes = hex::decode(&proof_bytes).map_err(|_| ContractError::ParseHex(proof_bytes))?;
             let public_values = hex::decode(&proof.public_values).map_err(|_| ContractError::ParseHex(proof.public_values))?;
-            let program_vkey = hex::decode(&program_vkey).map_err(|_| ContractError::ParseHex(program_vkey))?;
-
-            let received_selector =
-                u32::from_be_bytes(proof_bytes[0..4].to_vec().try_into().unwrap());
-            let expected_selector =
-                u32::from_be_bytes(verifier_hash[0..4].to_vec().try_into().unwrap());
-
-            if received_selector != expected_selector {
-                return Err(InvalidProof(format!(
-                    "wrong verifier selector {}, {}",
-                    received_selector, expected_selector
-                )));
-            }
-
-            let public_values_digest = hash_public_values(&public_values)?;
-            let inputs = [
-                BigUint::from_be_bytes(&program_vkey),
-                public_values_digest,
-            ];
-
-            let mut proof = [BigUint::ZERO; 8];
-            for i in 0..8 {
-                let offset = 4 + i * 32;
-                let element = BigUint::from_be_bytes(
-                    &proof_bytes[offset..offset + 32]
-                );
-                proof[i] = element;
-            }
-            PROOF_CONSTANTS.with(|proof_consts| {
-                verify_groth16_proof(&proof, &inputs, &proof_consts.borrow())
+            let vkey = hex::decode(&program_vkey).map_err(|_| ContractError::ParseHex(program_vkey.clone()))?;
+            verify_with_receipt(program_vkey, &proof_bytes, &public_values, caller, timestamp, || {
+                verify_sp1_groth16(&proof_bytes, &public_values, &vkey)
             })
         }
     }
 }
 
+/// Verifies an SP1 proof and returns the id of its verification receipt.
+#[cfg_attr(not(feature = "library"), ic_cdk::update)]
+pub fn verify_proof(proof: SP1ProofWithPublicValues, program_vkey: String) -> Result<u64, ContractError> {
+    let receipt_id = verify_sp1_proof(proof, program_vkey, api::caller(), api::time())?;
+    certify_receipts();
+    Ok(receipt_id)
+}
+
+/// Verifies an SP1 proof and decodes its public values, one value per `schema` entry.
+#[cfg_attr(not(feature = "library"), ic_cdk::update)]
+pub fn verify_proof_and_decode(
+    proof: SP1ProofWithPublicValues,
+    program_vkey: String,
+    schema: Vec<PublicValuesSchema>,
+) -> Result<DecodedPublicValues, ContractError> {
+    let public_values = hex::decode(&proof.public_values)
+        .map_err(|_| ContractError::ParseHex(proof.public_values.clone()))?;
+    let values = decode_with_schema(&public_values, &schema)?;
+    let receipt_id = verify_proof(proof, program_vkey)?;
+    Ok(DecodedPublicValues { receipt_id, values })
+}
+
+/// Registers a snarkjs-format verifying key (JSON) under `vk_id`. Ids can't be reused, so a
+/// receipt always refers to the key it was verified with.
+#[cfg_attr(not(feature = "library"), ic_cdk::update)]
+pub fn register_verifying_key(vk_id: String, vk_json: String) -> Result<(), ContractError> {
+    if !api::is_controller(&api::caller()) {
+        return Err(ContractError::Unauthorized(
+            "only controllers can register a verifying key".to_string(),
+        ));
+    }
+    if VERIFYING_KEYS.with_borrow(|keys| keys.contains_key(&vk_id)) {
+        return Err(ContractError::InvalidVerifyingKey(format!("{vk_id} is already registered")));
+    }
+    let vk = serde_json::from_str::<Groth16VerifyingKey>(&vk_json)
+        .map_err(|e| ContractError::InvalidVerifyingKey(e.to_string()))?;
+    let proof_consts = proof_consts_from_verifying_key(&vk)?;
+    VERIFYING_KEYS.with_borrow_mut(|keys| keys.insert(vk_id, proof_consts));
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), ic_cdk::query)]
+pub fn get_verifying_key_ids() -> Vec<String> {
+    VERIFYING_KEYS.with_borrow(|keys| keys.keys().cloned().collect())
+}
+
+/// Verifies a Groth16 proof against a registered verifying key and returns the id of its
+/// verification receipt.
+#[cfg_attr(not(feature = "library"), ic_cdk::update)]
+pub fn verify_groth16(
+    vk_id: String,
+    proof: Groth16Proof,
+    public_inputs: Vec<String>,
+) -> Result<u64, ContractError> {
+    let proof = proof_from_groth16_proof(&proof)?;
+    let public_inputs = parse_public_inputs(&public_inputs)?;
+
+    let to_bytes = |elements: &[BigUint]| -> Vec<u8> {
+        elements
+            .iter()
+            .flat_map(|x| {
+                let bytes = x.to_bytes_be();
+                let mut padded = vec![0u8; 32usize.saturating_sub(bytes.len())];
+                padded.extend(bytes);
+                padded
+            })
+            .collect()
+    };
+    let (proof_bytes, public_values) = (to_bytes(&proof), to_bytes(&public_inputs));
+
+    let receipt_id = verify_with_receipt(vk_id.clone(), &proof_bytes, &public_values, api::caller(), api::time(), || {
+        VERIFYING_KEYS.with_borrow(|keys| {
+            let proof_consts = keys
+                .get(&vk_id)
+                .ok_or(ContractError::VerifyingKeyNotFound(vk_id.clone()))?;
+            verify_groth16_proof(&proof, &public_inputs, proof_consts)
+        })
+    })?;
+    certify_receipts();
+    Ok(receipt_id)
+}
+
+#[cfg_attr(not(feature = "library"), ic_cdk::query)]
+pub fn get_receipt(receipt_id: u64) -> Option<VerificationReceipt> {
+    RECEIPTS.with_borrow(|receipts| receipts.get(receipt_id as usize).cloned())
+}
+
+/// Looks up the receipt of a proof by its hex encoded digest.
+#[cfg_attr(not(feature = "library"), ic_cdk::query)]
+pub fn find_receipt(proof_digest: String) -> Option<VerificationReceipt> {
+    let id = VERIFIED_PROOFS.with_borrow(|proofs| proofs.get(&proof_digest).copied())?;
+    get_receipt(id)
+}
+
+/// Returns a receipt with a certificate and witness, so clients can check it without trusting the
+/// replica that answered. Must be called as a query.
+#[cfg_attr(not(feature = "library"), ic_cdk::query)]
+pub fn get_certified_receipt(receipt_id: u64) -> Result<CertifiedReceipt, ContractError> {
+    let receipt = get_receipt(receipt_id).ok_or(ContractError::ReceiptNotFound(receipt_id))?;
+    let certificate = api::data_certificate().ok_or(ContractError::CertificateUnavailable)?;
+    Ok(CertifiedReceipt {
+        receipt,
+        certificate,
+        witness: receipt_witness(receipt_id),
+    })
+}
+
 #[cfg(not(feature = "library"))]
 ic_cdk::export_candid!();
 
 #[cfg(test)]
 mod tests {
-    use crate::contract::verify_proof;
+    use crate::contract::{find_receipt, get_receipt, receipt_witness, verify_sp1_proof};
+    use crate::state::{StableState, RECEIPTS, RECEIPT_TREE, VERIFIED_PROOFS};
+    use ic_certified_map::AsHashTree;
     use crate::types::SP1ProofWithPublicValues;
+    use crate::utils::{receipt_hash, receipt_key};
+    use candid::Principal;
 
     #[test]
     fn test_verify_groth16_proof() {
@@ -143,6 +239,29 @@ mod tests {
         
         let program_vkey = "005c2cee42b6c34b25cebc2c6d2f5b9a7080bb31ca929d98f1deaddd2d842b62".to_string();
         
-        verify_proof(proof, program_vkey).unwrap();
+        let receipt_id = verify_sp1_proof(proof.clone(), program_vkey.clone(), Principal::anonymous(), 1).unwrap();
+        let receipt = get_receipt(receipt_id).unwrap();
+        assert_eq!(receipt.vk_id, program_vkey);
+        assert_eq!(receipt.timestamp, 1);
+        assert_eq!(find_receipt(receipt.proof_digest.clone()), Some(receipt.clone()));
+        RECEIPT_TREE.with_borrow(|tree| {
+            assert_eq!(tree.get(&receipt_key(receipt_id)), Some(&receipt_hash(&receipt)));
+        });
+        assert!(!receipt_witness(receipt_id).is_empty());
+
+        // verifying the same proof again returns the cached receipt
+        let cached_id = verify_sp1_proof(proof, program_vkey, Principal::management_canister(), 2).unwrap();
+        assert_eq!(cached_id, receipt_id);
+        assert_eq!(get_receipt(cached_id), Some(receipt.clone()));
+
+        // an upgrade keeps the receipts and rebuilds the index and the tree
+        let root_hash = RECEIPT_TREE.with_borrow(|tree| tree.root_hash());
+        let saved = bincode::serialize(&StableState::save()).unwrap();
+        RECEIPTS.with_borrow_mut(|receipts| receipts.clear());
+        VERIFIED_PROOFS.with_borrow_mut(|proofs| proofs.clear());
+        bincode::deserialize::<StableState>(&saved).unwrap().restore();
+        assert_eq!(get_receipt(receipt_id), Some(receipt.clone()));
+        assert_eq!(find_receipt(receipt.proof_digest), Some(get_receipt(receipt_id).unwrap()));
+        assert_eq!(RECEIPT_TREE.with_borrow(|tree| tree.root_hash()), root_hash);
     }
 }
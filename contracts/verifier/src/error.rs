@@ -22,4 +22,25 @@ pub enum ContractError {
 
     #[error("Pairing failed")]
     PairingFailed,
+
+    #[error("Invalid verifying key: {0}")]
+    InvalidVerifyingKey(String),
+
+    #[error("Verifying key {0} not found")]
+    VerifyingKeyNotFound(String),
+
+    #[error("Invalid public inputs: {0}")]
+    InvalidPublicInputs(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Receipt {0} not found")]
+    ReceiptNotFound(u64),
+
+    #[error("Data certificate is only available in query calls")]
+    CertificateUnavailable,
+
+    #[error("Invalid public values: {0}")]
+    InvalidPublicValues(String),
 }
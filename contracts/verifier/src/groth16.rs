@@ -1,11 +1,11 @@
 use crate::bn254::my_fq12::MyFq12;
 use crate::bn254::pairing::pairing;
 use crate::error::ContractError;
-use crate::error::ContractError::PairingFailed;
-use crate::types::{BaseField, ProofConsts, ScalarField};
+use crate::error::ContractError::{InvalidProof, PairingFailed};
+use crate::types::{BaseField, Groth16Proof, Groth16VerifyingKey, ProofConsts, ScalarField};
 use ark_bn254::{Fq12, Fq2, G1Affine, G2Affine};
 use num_bigint::BigUint;
-use num_traits::One;
+use num_traits::{FromBytes, One, Zero};
 use std::panic;
 use std::str::FromStr;
 
@@ -43,13 +43,213 @@ pub fn g2_from_xy(
     result.map_err(|_e| ContractError::PointNotOnCurve)
 }
 
+pub const SCALAR_FIELD_MODULUS: &str =
+    "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+pub const BASE_FIELD_MODULUS: &str =
+    "21888242871839275222246405745257275088696311157297823662689037894645226208583";
+
+fn parse_decimal(x: &str) -> Option<BigUint> {
+    BigUint::from_str(x).ok()
+}
+
+fn neg_base_field(x: &BigUint, q: &BigUint) -> BigUint {
+    if x.is_zero() {
+        BigUint::ZERO
+    } else {
+        q - x
+    }
+}
+
+fn parse_g1(point: &[String], name: &str) -> Result<(BigUint, BigUint), ContractError> {
+    match point {
+        [x, y, ..] => Ok((
+            parse_decimal(x).ok_or(ContractError::InvalidVerifyingKey(format!("{name}.x")))?,
+            parse_decimal(y).ok_or(ContractError::InvalidVerifyingKey(format!("{name}.y")))?,
+        )),
+        _ => Err(ContractError::InvalidVerifyingKey(format!("{name} is not a G1 point"))),
+    }
+}
+
+/// Returns `(x_c0, x_c1, y_c0, y_c1)` of a snarkjs encoded G2 point
+fn parse_g2(
+    point: &[Vec<String>],
+    name: &str,
+) -> Result<(BigUint, BigUint, BigUint, BigUint), ContractError> {
+    match point {
+        [x, y, ..] if x.len() == 2 && y.len() == 2 => {
+            let parse = |v: &str, part: &str| {
+                parse_decimal(v).ok_or(ContractError::InvalidVerifyingKey(format!("{name}.{part}")))
+            };
+            Ok((
+                parse(&x[0], "x.c0")?,
+                parse(&x[1], "x.c1")?,
+                parse(&y[0], "y.c0")?,
+                parse(&y[1], "y.c1")?,
+            ))
+        }
+        _ => Err(ContractError::InvalidVerifyingKey(format!("{name} is not a G2 point"))),
+    }
+}
+
+/// Converts a snarkjs verifying key into the constants used by [`verify_groth16_proof`].
+pub fn proof_consts_from_verifying_key(
+    vk: &Groth16VerifyingKey,
+) -> Result<ProofConsts, ContractError> {
+    if vk.protocol != "groth16" {
+        return Err(ContractError::InvalidVerifyingKey(format!("unsupported protocol {}", vk.protocol)));
+    }
+    if vk.curve != "bn128" && vk.curve != "bn254" {
+        return Err(ContractError::InvalidVerifyingKey(format!("unsupported curve {}", vk.curve)));
+    }
+    if vk.ic.len() != vk.n_public + 1 {
+        return Err(ContractError::InvalidVerifyingKey(format!(
+            "expected {} IC points, got {}",
+            vk.n_public + 1,
+            vk.ic.len()
+        )));
+    }
+
+    let q = BigUint::from_str(BASE_FIELD_MODULUS).unwrap();
+    let (alpha_x, alpha_y) = parse_g1(&vk.vk_alpha_1, "vk_alpha_1")?;
+    let (beta_x_c0, beta_x_c1, beta_y_c0, beta_y_c1) = parse_g2(&vk.vk_beta_2, "vk_beta_2")?;
+    let (gamma_x_c0, gamma_x_c1, gamma_y_c0, gamma_y_c1) = parse_g2(&vk.vk_gamma_2, "vk_gamma_2")?;
+    let (delta_x_c0, delta_x_c1, delta_y_c0, delta_y_c1) = parse_g2(&vk.vk_delta_2, "vk_delta_2")?;
+
+    let mut ic_x = Vec::with_capacity(vk.ic.len());
+    let mut ic_y = Vec::with_capacity(vk.ic.len());
+    for (i, point) in vk.ic.iter().enumerate() {
+        let (x, y) = parse_g1(point, &format!("IC[{i}]"))?;
+        ic_x.push(x);
+        ic_y.push(y);
+    }
+
+    let proof_consts = ProofConsts {
+        r: BigUint::from_str(SCALAR_FIELD_MODULUS).unwrap(),
+        alpha_x,
+        alpha_y,
+        beta_x_0: beta_x_c1,
+        beta_x_1: beta_x_c0,
+        beta_y_0: neg_base_field(&beta_y_c1, &q),
+        beta_y_1: neg_base_field(&beta_y_c0, &q),
+        gamma_x_0: gamma_x_c1,
+        gamma_x_1: gamma_x_c0,
+        gamma_y_0: neg_base_field(&gamma_y_c1, &q),
+        gamma_y_1: neg_base_field(&gamma_y_c0, &q),
+        delta_x_0: delta_x_c1,
+        delta_x_1: delta_x_c0,
+        delta_y_0: neg_base_field(&delta_y_c1, &q),
+        delta_y_1: neg_base_field(&delta_y_c0, &q),
+        ic_x,
+        ic_y,
+        q,
+    };
+    validate_proof_consts(&proof_consts)?;
+    Ok(proof_consts)
+}
+
+/// Checks that every point of the verifying key is on the curve.
+pub fn validate_proof_consts(proof_consts: &ProofConsts) -> Result<(), ContractError> {
+    let coordinates = [
+        &proof_consts.alpha_x,
+        &proof_consts.alpha_y,
+        &proof_consts.beta_x_0,
+        &proof_consts.beta_x_1,
+        &proof_consts.beta_y_0,
+        &proof_consts.beta_y_1,
+        &proof_consts.gamma_x_0,
+        &proof_consts.gamma_x_1,
+        &proof_consts.gamma_y_0,
+        &proof_consts.gamma_y_1,
+        &proof_consts.delta_x_0,
+        &proof_consts.delta_x_1,
+        &proof_consts.delta_y_0,
+        &proof_consts.delta_y_1,
+    ];
+    for x in coordinates
+        .into_iter()
+        .chain(&proof_consts.ic_x)
+        .chain(&proof_consts.ic_y)
+    {
+        if x >= &proof_consts.q {
+            return Err(ContractError::NotInBaseField(x.to_string()));
+        }
+    }
+
+    let _ = g1_from_xy(&proof_consts.alpha_x, &proof_consts.alpha_y)?;
+    let _ = g2_from_xy(&proof_consts.beta_x_1, &proof_consts.beta_x_0, &proof_consts.beta_y_1, &proof_consts.beta_y_0)?;
+    let _ = g2_from_xy(&proof_consts.gamma_x_1, &proof_consts.gamma_x_0, &proof_consts.gamma_y_1, &proof_consts.gamma_y_0)?;
+    let _ = g2_from_xy(&proof_consts.delta_x_1, &proof_consts.delta_x_0, &proof_consts.delta_y_1, &proof_consts.delta_y_0)?;
+    for (x, y) in proof_consts.ic_x.iter().zip(&proof_consts.ic_y) {
+        let _ = g1_from_xy(x, y)?;
+    }
+    Ok(())
+}
+
+/// Decodes a proof into `[a.x, a.y, b.x_c1, b.x_c0, b.y_c1, b.y_c0, c.x, c.y]`.
+pub fn proof_from_groth16_proof(proof: &Groth16Proof) -> Result<[BigUint; 8], ContractError> {
+    match proof {
+        Groth16Proof::SnarkJs(proof) => {
+            let (pi_b_x, pi_b_y) = match proof.pi_b.as_slice() {
+                [x, y, ..] if x.len() == 2 && y.len() == 2 => (x, y),
+                _ => return Err(InvalidProof("pi_b is not a G2 point".to_string())),
+            };
+            if proof.pi_a.len() < 2 || proof.pi_c.len() < 2 {
+                return Err(InvalidProof("pi_a or pi_c is not a G1 point".to_string()));
+            }
+            let elements = [
+                &proof.pi_a[0],
+                &proof.pi_a[1],
+                &pi_b_x[1],
+                &pi_b_x[0],
+                &pi_b_y[1],
+                &pi_b_y[0],
+                &proof.pi_c[0],
+                &proof.pi_c[1],
+            ];
+            let mut result = [BigUint::ZERO; 8];
+            for (element, value) in result.iter_mut().zip(elements) {
+                *element = parse_decimal(value)
+                    .ok_or(InvalidProof(format!("{value} is not a decimal number")))?;
+            }
+            Ok(result)
+        }
+        Groth16Proof::Gnark(proof_hex) => {
+            let proof_bytes =
+                hex::decode(proof_hex).map_err(|_| ContractError::ParseHex(proof_hex.clone()))?;
+            if proof_bytes.len() != 8 * 32 {
+                return Err(InvalidProof(format!(
+                    "wrong proof length {}, {}",
+                    proof_bytes.len(),
+                    8 * 32
+                )));
+            }
+            let mut result = [BigUint::ZERO; 8];
+            for (element, chunk) in result.iter_mut().zip(proof_bytes.chunks(32)) {
+                *element = BigUint::from_be_bytes(chunk);
+            }
+            Ok(result)
+        }
+    }
+}
+
+/// Parses public inputs given as decimal strings, as in snarkjs `public.json`.
+pub fn parse_public_inputs(public_inputs: &[String]) -> Result<Vec<BigUint>, ContractError> {
+    public_inputs
+        .iter()
+        .map(|x| {
+            parse_decimal(x)
+                .ok_or(ContractError::InvalidPublicInputs(format!("{x} is not a decimal number")))
+        })
+        .collect()
+}
+
 pub fn verify_groth16_proof(
     proof: &[BigUint; 8],
-    inputs: &[BigUint; 2],
+    inputs: &[BigUint],
     proof_consts: &ProofConsts,
 ) -> Result<(), ContractError> {
     let ProofConsts {
-        r: _r,
+        r,
         q,
         alpha_x,
         alpha_y,
@@ -69,8 +269,21 @@ pub fn verify_groth16_proof(
         ic_y,
     } = proof_consts;
 
+    if inputs.len() + 1 != ic_x.len() {
+        return Err(ContractError::InvalidPublicInputs(format!(
+            "expected {} public inputs, got {}",
+            ic_x.len().saturating_sub(1),
+            inputs.len()
+        )));
+    }
+
     // Validate that all evaluations ∈ F
     for x in inputs {
+        if x >= r {
+            return Err(ContractError::NotInScalarField(x.to_string()));
+        }
+    }
+    for x in proof {
         if x >= q {
             return Err(ContractError::NotInBaseField(x.to_string()));
         }
@@ -102,3 +315,132 @@ pub fn verify_groth16_proof(
         Err(PairingFailed)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ContractError;
+    use crate::groth16::{
+        parse_public_inputs, proof_consts_from_verifying_key, proof_from_groth16_proof,
+        verify_groth16_proof,
+    };
+    use crate::types::{Groth16Proof, Groth16VerifyingKey, SnarkJsProof};
+    use ark_bn254::{Fr, G1Affine, G2Affine};
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_ff::{BigInteger, Field, PrimeField};
+
+    fn g1(k: Fr) -> G1Affine {
+        (G1Affine::generator() * k).into_affine()
+    }
+
+    fn g2(k: Fr) -> G2Affine {
+        (G2Affine::generator() * k).into_affine()
+    }
+
+    fn g1_json(p: G1Affine) -> Vec<String> {
+        vec![p.x.to_string(), p.y.to_string(), "1".to_string()]
+    }
+
+    fn g2_json(p: G2Affine) -> Vec<Vec<String>> {
+        vec![
+            vec![p.x.c0.to_string(), p.x.c1.to_string()],
+            vec![p.y.c0.to_string(), p.y.c1.to_string()],
+            vec!["1".to_string(), "0".to_string()],
+        ]
+    }
+
+    /// Builds a verifying key and a valid proof for `inputs` by choosing every discrete log,
+    /// so that `a * b = alpha * beta + l * gamma + c * delta` holds.
+    fn synthetic_instance(inputs: &[Fr]) -> (Groth16VerifyingKey, SnarkJsProof) {
+        let (a, b, alpha, beta, gamma, delta) = (
+            Fr::from(3u64),
+            Fr::from(5u64),
+            Fr::from(7u64),
+            Fr::from(11u64),
+            Fr::from(13u64),
+            Fr::from(17u64),
+        );
+        let ic: Vec<Fr> = (0..=inputs.len()).map(|i| Fr::from(19u64 + i as u64)).collect();
+        let l = inputs
+            .iter()
+            .zip(&ic[1..])
+            .fold(ic[0], |acc, (x, k)| acc + *x * k);
+        let c = (a * b - alpha * beta - l * gamma) * delta.inverse().unwrap();
+
+        let vk = Groth16VerifyingKey {
+            protocol: "groth16".to_string(),
+            curve: "bn128".to_string(),
+            n_public: inputs.len(),
+            vk_alpha_1: g1_json(g1(alpha)),
+            vk_beta_2: g2_json(g2(beta)),
+            vk_gamma_2: g2_json(g2(gamma)),
+            vk_delta_2: g2_json(g2(delta)),
+            ic: ic.into_iter().map(|k| g1_json(g1(k))).collect(),
+        };
+        let proof = SnarkJsProof {
+            pi_a: g1_json(g1(a)),
+            pi_b: g2_json(g2(b)),
+            pi_c: g1_json(g1(c)),
+        };
+        (vk, proof)
+    }
+
+    fn fr_strings(inputs: &[Fr]) -> Vec<String> {
+        inputs.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn test_verify_snarkjs_proof_with_three_inputs() {
+        let inputs = [Fr::from(2u64), Fr::from(4u64), Fr::from(6u64)];
+        let (vk, proof) = synthetic_instance(&inputs);
+
+        let proof_consts = proof_consts_from_verifying_key(&vk).unwrap();
+        let proof = proof_from_groth16_proof(&Groth16Proof::SnarkJs(proof)).unwrap();
+        let public_inputs = parse_public_inputs(&fr_strings(&inputs)).unwrap();
+        verify_groth16_proof(&proof, &public_inputs, &proof_consts).unwrap();
+
+        let wrong_inputs = parse_public_inputs(&fr_strings(&[inputs[0], inputs[1], inputs[0]])).unwrap();
+        assert!(matches!(
+            verify_groth16_proof(&proof, &wrong_inputs, &proof_consts),
+            Err(ContractError::PairingFailed)
+        ));
+        assert!(matches!(
+            verify_groth16_proof(&proof, &public_inputs[..2], &proof_consts),
+            Err(ContractError::InvalidPublicInputs(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_gnark_encoded_proof() {
+        let inputs = [Fr::from(42u64)];
+        let (vk, proof) = synthetic_instance(&inputs);
+        let (a, b, c) = (g1(Fr::from(3u64)), g2(Fr::from(5u64)), proof.pi_c);
+
+        let mut proof_bytes = vec![];
+        for x in [a.x, a.y, b.x.c1, b.x.c0, b.y.c1, b.y.c0] {
+            proof_bytes.extend(x.into_bigint().to_bytes_be());
+        }
+        for x in c.iter().take(2) {
+            let x: ark_bn254::Fq = x.parse().unwrap();
+            proof_bytes.extend(x.into_bigint().to_bytes_be());
+        }
+
+        let proof_consts = proof_consts_from_verifying_key(&vk).unwrap();
+        let proof = proof_from_groth16_proof(&Groth16Proof::Gnark(hex::encode(proof_bytes))).unwrap();
+        let public_inputs = parse_public_inputs(&fr_strings(&inputs)).unwrap();
+        verify_groth16_proof(&proof, &public_inputs, &proof_consts).unwrap();
+    }
+
+    #[test]
+    fn test_parse_verifying_key_json() {
+        let (vk, _) = synthetic_instance(&[Fr::from(1u64)]);
+        let json = serde_json::to_string(&vk).unwrap();
+        assert!(json.contains("\"nPublic\":1") && json.contains("\"IC\":"));
+
+        let mut vk = serde_json::from_str::<Groth16VerifyingKey>(&json).unwrap();
+        vk.n_public = 2;
+        assert!(matches!(
+            proof_consts_from_verifying_key(&vk),
+            Err(ContractError::InvalidVerifyingKey(_))
+        ));
+    }
+}
@@ -2,5 +2,10 @@ pub mod bn254;
 pub mod groth16;
 pub mod error;
 pub mod contract;
+pub mod public_values;
+pub mod sp1;
+pub mod state;
 pub mod types;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+
+pub use sp1::verify_sp1_groth16;
\ No newline at end of file
@@ -0,0 +1,249 @@
+use crate::error::ContractError;
+use crate::error::ContractError::InvalidPublicValues;
+use candid::CandidType;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Shape of a value committed by an SP1 program with `sp1_zkvm::io::commit`.
+///
+/// Committed values are serialized with bincode: integers are fixed size little endian, sequences
+/// and strings are prefixed with their length as a `u64`, tuples and arrays are not prefixed.
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq)]
+pub enum PublicValuesSchema {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I32,
+    I64,
+    /// `Vec<u8>`
+    Bytes,
+    String,
+    Option(Box<PublicValuesSchema>),
+    Vec(Box<PublicValuesSchema>),
+    /// Fixed size array such as `[u8; 32]`
+    Array(u64, Box<PublicValuesSchema>),
+    Tuple(Vec<PublicValuesSchema>),
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq)]
+pub enum PublicValue {
+    Bool(bool),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    I32(i32),
+    I64(i64),
+    Bytes(Vec<u8>),
+    String(String),
+    Option(Option<Box<PublicValue>>),
+    Vec(Vec<PublicValue>),
+    Array(Vec<PublicValue>),
+    Tuple(Vec<PublicValue>),
+}
+
+/// Decodes the first value committed by an SP1 program, ignoring anything committed after it.
+pub fn decode_public_values<T: DeserializeOwned>(public_values: &[u8]) -> Result<T, ContractError> {
+    bincode::deserialize(public_values).map_err(|e| InvalidPublicValues(e.to_string()))
+}
+
+/// Decodes the public values of the sudoku program, which commits the initial state of the puzzle.
+pub fn decode_sudoku_initial_state(public_values: &[u8]) -> Result<Vec<(u8, u8)>, ContractError> {
+    decode_public_values(public_values)
+}
+
+/// Decodes public values committed as one value per schema entry. All bytes must be consumed.
+pub fn decode_with_schema(
+    public_values: &[u8],
+    schema: &[PublicValuesSchema],
+) -> Result<Vec<PublicValue>, ContractError> {
+    let mut reader = Reader {
+        bytes: public_values,
+        offset: 0,
+    };
+    let values = schema
+        .iter()
+        .map(|schema| reader.read(schema))
+        .collect::<Result<Vec<_>, _>>()?;
+    if reader.offset != public_values.len() {
+        return Err(InvalidPublicValues(format!(
+            "{} trailing bytes",
+            public_values.len() - reader.offset
+        )));
+    }
+    Ok(values)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], ContractError> {
+        Ok(self.take_slice(N)?.try_into().unwrap())
+    }
+
+    fn take_slice(&mut self, len: usize) -> Result<&[u8], ContractError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(InvalidPublicValues(format!(
+                "unexpected end of input at byte {}",
+                self.offset
+            )))?;
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn read_len(&mut self) -> Result<usize, ContractError> {
+        let len = u64::from_le_bytes(self.take()?);
+        usize::try_from(len).map_err(|_| InvalidPublicValues(format!("length {len} is too large")))
+    }
+
+    fn read_many(
+        &mut self,
+        len: usize,
+        schema: &PublicValuesSchema,
+    ) -> Result<Vec<PublicValue>, ContractError> {
+        // guards against huge lengths, real public values never contain zero sized elements
+        if len > self.bytes.len() - self.offset {
+            return Err(InvalidPublicValues(format!(
+                "length {len} exceeds the remaining input"
+            )));
+        }
+        (0..len).map(|_| self.read(schema)).collect()
+    }
+
+    fn read(&mut self, schema: &PublicValuesSchema) -> Result<PublicValue, ContractError> {
+        Ok(match schema {
+            PublicValuesSchema::Bool => match self.take::<1>()?[0] {
+                0 => PublicValue::Bool(false),
+                1 => PublicValue::Bool(true),
+                x => return Err(InvalidPublicValues(format!("invalid bool {x}"))),
+            },
+            PublicValuesSchema::U8 => PublicValue::U8(self.take::<1>()?[0]),
+            PublicValuesSchema::U16 => PublicValue::U16(u16::from_le_bytes(self.take()?)),
+            PublicValuesSchema::U32 => PublicValue::U32(u32::from_le_bytes(self.take()?)),
+            PublicValuesSchema::U64 => PublicValue::U64(u64::from_le_bytes(self.take()?)),
+            PublicValuesSchema::U128 => PublicValue::U128(u128::from_le_bytes(self.take()?)),
+            PublicValuesSchema::I32 => PublicValue::I32(i32::from_le_bytes(self.take()?)),
+            PublicValuesSchema::I64 => PublicValue::I64(i64::from_le_bytes(self.take()?)),
+            PublicValuesSchema::Bytes => {
+                let len = self.read_len()?;
+                PublicValue::Bytes(self.take_slice(len)?.to_vec())
+            }
+            PublicValuesSchema::String => {
+                let len = self.read_len()?;
+                let bytes = self.take_slice(len)?.to_vec();
+                PublicValue::String(
+                    String::from_utf8(bytes).map_err(|e| InvalidPublicValues(e.to_string()))?,
+                )
+            }
+            PublicValuesSchema::Option(schema) => match self.take::<1>()?[0] {
+                0 => PublicValue::Option(None),
+                1 => PublicValue::Option(Some(Box::new(self.read(schema)?))),
+                x => return Err(InvalidPublicValues(format!("invalid option tag {x}"))),
+            },
+            PublicValuesSchema::Vec(schema) => {
+                let len = self.read_len()?;
+                PublicValue::Vec(self.read_many(len, schema)?)
+            }
+            PublicValuesSchema::Array(len, schema) => {
+                let len = usize::try_from(*len)
+                    .map_err(|_| InvalidPublicValues(format!("length {len} is too large")))?;
+                PublicValue::Array(self.read_many(len, schema)?)
+            }
+            PublicValuesSchema::Tuple(schemas) => PublicValue::Tuple(
+                schemas
+                    .iter()
+                    .map(|schema| self.read(schema))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ContractError;
+    use crate::public_values::{
+        decode_public_values, decode_sudoku_initial_state, decode_with_schema, PublicValue,
+        PublicValuesSchema,
+    };
+
+    const PUBLIC_VALUES: &str = "05000000000000000008010707090e081101";
+
+    fn initial_state() -> Vec<(u8, u8)> {
+        vec![(0, 8), (1, 7), (7, 9), (14, 8), (17, 1)]
+    }
+
+    #[test]
+    fn test_decode_sudoku_initial_state() {
+        let public_values = hex::decode(PUBLIC_VALUES).unwrap();
+        assert_eq!(decode_sudoku_initial_state(&public_values).unwrap(), initial_state());
+    }
+
+    #[test]
+    fn test_decode_with_schema() {
+        let mut public_values = hex::decode(PUBLIC_VALUES).unwrap();
+        public_values.extend(bincode::serialize(&(Some("ok".to_string()), 7u32, [1u8; 2])).unwrap());
+
+        let schema = [
+            PublicValuesSchema::Vec(Box::new(PublicValuesSchema::Tuple(vec![
+                PublicValuesSchema::U8,
+                PublicValuesSchema::U8,
+            ]))),
+            PublicValuesSchema::Tuple(vec![
+                PublicValuesSchema::Option(Box::new(PublicValuesSchema::String)),
+                PublicValuesSchema::U32,
+                PublicValuesSchema::Array(2, Box::new(PublicValuesSchema::U8)),
+            ]),
+        ];
+        let values = decode_with_schema(&public_values, &schema).unwrap();
+
+        let cells = initial_state()
+            .into_iter()
+            .map(|(position, value)| {
+                PublicValue::Tuple(vec![PublicValue::U8(position), PublicValue::U8(value)])
+            })
+            .collect();
+        assert_eq!(values[0], PublicValue::Vec(cells));
+        assert_eq!(
+            values[1],
+            PublicValue::Tuple(vec![
+                PublicValue::Option(Some(Box::new(PublicValue::String("ok".to_string())))),
+                PublicValue::U32(7),
+                PublicValue::Array(vec![PublicValue::U8(1), PublicValue::U8(1)]),
+            ])
+        );
+
+        // the first value can still be read on its own
+        assert_eq!(decode_public_values::<Vec<(u8, u8)>>(&public_values).unwrap(), initial_state());
+    }
+
+    #[test]
+    fn test_decode_with_schema_rejects_malformed_input() {
+        let public_values = hex::decode(PUBLIC_VALUES).unwrap();
+        let schema = PublicValuesSchema::Vec(Box::new(PublicValuesSchema::U16));
+
+        assert!(matches!(
+            decode_with_schema(&public_values[..12], std::slice::from_ref(&schema)),
+            Err(ContractError::InvalidPublicValues(_))
+        ));
+        assert!(matches!(
+            decode_with_schema(&public_values, &[PublicValuesSchema::U64]),
+            Err(ContractError::InvalidPublicValues(_))
+        ));
+        assert!(matches!(
+            decode_with_schema(&u64::MAX.to_le_bytes(), &[schema]),
+            Err(ContractError::InvalidPublicValues(_))
+        ));
+    }
+}
@@ -0,0 +1,50 @@
+use crate::types::{ProofConsts, VerificationReceipt};
+use crate::utils::{receipt_hash, receipt_key};
+use ic_certified_map::{Hash, RbTree};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+thread_local! {
+    pub(crate) static VERIFYING_KEYS: RefCell<BTreeMap<String, ProofConsts>> = const { RefCell::new(BTreeMap::new()) };
+    /// RECEIPTS[receipt id] = receipt
+    pub(crate) static RECEIPTS: RefCell<Vec<VerificationReceipt>> = const { RefCell::new(Vec::new()) };
+    /// VERIFIED_PROOFS[proof digest] = receipt id
+    pub(crate) static VERIFIED_PROOFS: RefCell<BTreeMap<String, u64>> = const { RefCell::new(BTreeMap::new()) };
+    /// RECEIPT_TREE[receipt key] = receipt hash, its labeled root hash is the canister's certified data
+    pub(crate) static RECEIPT_TREE: RefCell<RbTree<Vec<u8>, Hash>> = const { RefCell::new(RbTree::new()) };
+}
+/// What the canister keeps in stable memory across upgrades. The proof index and the receipts
+/// tree are rebuilt from the receipts.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct StableState {
+    verifying_keys: BTreeMap<String, ProofConsts>,
+    receipts: Vec<VerificationReceipt>,
+}
+
+impl StableState {
+    pub(crate) fn save() -> Self {
+        Self {
+            verifying_keys: VERIFYING_KEYS.with_borrow(|keys| keys.clone()),
+            receipts: RECEIPTS.with_borrow(|receipts| receipts.clone()),
+        }
+    }
+
+    /// Replaces the state of the canister, the certified data must be set again afterwards.
+    pub(crate) fn restore(self) {
+        VERIFIED_PROOFS.set(
+            self.receipts
+                .iter()
+                .map(|receipt| (receipt.proof_digest.clone(), receipt.id))
+                .collect(),
+        );
+        RECEIPT_TREE.with_borrow_mut(|tree| {
+            *tree = RbTree::new();
+            for receipt in &self.receipts {
+                tree.insert(receipt_key(receipt.id), receipt_hash(receipt));
+            }
+        });
+        RECEIPTS.set(self.receipts);
+        VERIFYING_KEYS.set(self.verifying_keys);
+    }
+}
@@ -1,5 +1,6 @@
+use crate::public_values::PublicValue;
 use ark_bn254::{Fq, Fr};
-use candid::CandidType;
+use candid::{CandidType, Principal};
 use num_bigint::BigUint;
 use serde::{Deserialize, Serialize};
 
@@ -7,18 +8,22 @@ pub type BaseField = Fq;
 pub type ScalarField = Fr;
 
 #[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct SP1ProofWithPublicValues {
     pub proof: SP1Proof,
     pub public_values: String,
 }
 
 #[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum SP1Proof {
     #[serde(rename = "groth16")]
     Groth16(String)
 }
 
-#[derive(Serialize, Deserialize)]
+/// Verifying key constants in the layout of gnark's Solidity verifier: the second coordinate of each
+/// G2 element comes first and `beta`, `gamma` and `delta` are stored negated.
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ProofConsts {
     pub r: BigUint,
     pub q: BigUint,
@@ -36,6 +41,73 @@ pub struct ProofConsts {
     pub delta_x_1: BigUint,
     pub delta_y_0: BigUint,
     pub delta_y_1: BigUint,
-    pub ic_x: [BigUint; 3],
-    pub ic_y: [BigUint; 3],
+    pub ic_x: Vec<BigUint>,
+    pub ic_y: Vec<BigUint>,
+}
+
+/// Groth16 verifying key in the snarkjs `verification_key.json` format.
+///
+/// Points are given as decimal strings in projective form (`[x, y, z]`), G2 coordinates as
+/// `[c0, c1]` pairs. gnark verifying keys can be exported to the same format.
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Groth16VerifyingKey {
+    pub protocol: String,
+    pub curve: String,
+    #[serde(rename = "nPublic")]
+    pub n_public: usize,
+    pub vk_alpha_1: Vec<String>,
+    pub vk_beta_2: Vec<Vec<String>>,
+    pub vk_gamma_2: Vec<Vec<String>>,
+    pub vk_delta_2: Vec<Vec<String>>,
+    #[serde(rename = "IC")]
+    pub ic: Vec<Vec<String>>,
+}
+
+/// Groth16 proof in the snarkjs `proof.json` format.
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct SnarkJsProof {
+    pub pi_a: Vec<String>,
+    pub pi_b: Vec<Vec<String>>,
+    pub pi_c: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub enum Groth16Proof {
+    #[serde(rename = "snarkjs")]
+    SnarkJs(SnarkJsProof),
+    /// Hex encoded 256 bytes `A || B || C` as produced by gnark's `MarshalSolidity`
+    #[serde(rename = "gnark")]
+    Gnark(String),
+}
+
+/// Record of a successful verification, kept by the verifier canister.
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct VerificationReceipt {
+    pub id: u64,
+    /// Hex encoded digest of the verifying key id, proof and public values
+    pub proof_digest: String,
+    pub vk_id: String,
+    /// Hex encoded SHA-256 of the public values
+    pub public_values_hash: String,
+    /// Nanoseconds since the Unix epoch
+    pub timestamp: u64,
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub caller: Principal,
+}
+
+/// A receipt together with the proof that it is part of the canister's certified data.
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct CertifiedReceipt {
+    pub receipt: VerificationReceipt,
+    /// CBOR encoded certificate of the subnet
+    pub certificate: Vec<u8>,
+    /// CBOR encoded hash tree witness of `receipts/<receipt id>`
+    pub witness: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct DecodedPublicValues {
+    pub receipt_id: u64,
+    pub values: Vec<PublicValue>,
 }
\ No newline at end of file
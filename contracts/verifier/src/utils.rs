@@ -3,6 +3,7 @@ use sha2::{Digest, Sha256};
 use num_bigint::BigUint;
 use num_traits::{FromBytes, ToBytes};
 use crate::error::ContractError;
+use crate::types::VerificationReceipt;
 
 fn and_bytes32(src: &[u8; 32], key: &[u8; 32]) -> [u8; 32] {
     let mut result = [0; 32];
@@ -17,4 +18,40 @@ pub fn hash_public_values(public_values: &[u8]) -> Result<BigUint, ContractError
         &Sha256::digest(public_values).to_vec().try_into().unwrap(),
         &BigUint::from_str("14474011154664524427946373126085988481658748083205070504932198000989141204991").unwrap().to_be_bytes().try_into().unwrap()
     )))
+}
+
+/// Digest identifying a verified proof. Every part is length-prefixed so different splits of the
+/// same bytes never collide.
+pub fn proof_digest(vk_id: &str, proof: &[u8], public_values: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in [vk_id.as_bytes(), proof, public_values] {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+/// Label of the certified receipts subtree.
+pub const RECEIPTS_LABEL: &[u8] = b"receipts";
+
+/// Key of a receipt in the certified receipts subtree.
+pub fn receipt_key(receipt_id: u64) -> Vec<u8> {
+    receipt_id.to_be_bytes().to_vec()
+}
+
+/// Value certified for a receipt, clients recompute it to check a receipt against a witness.
+pub fn receipt_hash(receipt: &VerificationReceipt) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(receipt.id.to_be_bytes());
+    for part in [
+        receipt.proof_digest.as_bytes(),
+        receipt.vk_id.as_bytes(),
+        receipt.public_values_hash.as_bytes(),
+        receipt.caller.as_slice(),
+    ] {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    hasher.update(receipt.timestamp.to_be_bytes());
+    hasher.finalize().into()
 }
\ No newline at end of file
@@ -1,6 +1,5 @@
 import { getStoragePanic, StorageKey } from '@/utils/storage';
 import { JsonRpcClient } from '@calimero-network/calimero-client';
-import { Principal } from '@dfinity/principal';
 import { isUndefined } from 'lodash';
 import { toast } from 'react-toastify';
 
@@ -25,11 +24,25 @@ export class SudokuCaller {
         this.client = calimeroClient(nodeUrl);
     }
 
-    async setCell(info: CellInfo) {
-        let res = await this.client.execute<CellInfo, void>({
+    async join(address: string, name: string, token: string) {
+        let res = await this.client.execute({
+            contextId: getStoragePanic(StorageKey.CONTEXT_ID),
+            method: "join",
+            argsJson: { address, name, token },
+            executorPublicKey: getJWTObject().executor_public_key
+        }, {
+            headers: getHeaders()
+        });
+        if (res.error) {
+            throw res.error;
+        }
+    }
+
+    async setCell(position: number, value: number) {
+        let res = await this.client.execute({
             contextId: getStoragePanic(StorageKey.CONTEXT_ID),
             method: "set_cell",
-            argsJson: info,
+            argsJson: { position, value },
             executorPublicKey: getJWTObject().executor_public_key
         }, {
             headers: getHeaders()
@@ -39,11 +52,11 @@ export class SudokuCaller {
         }
     }
 
-    async removeCell(info: Omit<CellInfo, 'value'>) {
+    async removeCell(position: number) {
         let res = await this.client.execute({
             contextId: getStoragePanic(StorageKey.CONTEXT_ID),
             method: "remove_cell",
-            argsJson: info,
+            argsJson: { position },
             executorPublicKey: getJWTObject().executor_public_key
         }, {
             headers: getHeaders()
@@ -99,13 +112,12 @@ export class SudokuCaller {
         }));
     }
 
-    async voteSolution(isPublic: boolean, caller: Principal): Promise<void> {
+    async voteSolution(isPublic: boolean): Promise<void> {
         let res = await this.client.execute<{}, void>({
             contextId: getStoragePanic(StorageKey.CONTEXT_ID),
             method: "vote_solution",
             argsJson: {
-                public: isPublic,
-                caller: caller.toString()
+                public: isPublic
             },
             executorPublicKey: getJWTObject().executor_public_key
         }, {
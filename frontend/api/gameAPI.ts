@@ -1,6 +1,7 @@
 import { toast } from "react-toastify";
 import { httpService } from "./httpService";
 import { Principal } from "@dfinity/principal";
+import { BattleEvent, battleEventTypes, ProofJob } from "@/types/game";
 
 export class GameAPI {
     static async getGameInfo(): Promise<{
@@ -17,6 +18,15 @@ export class GameAPI {
         return res;
     }
 
+    static async getTeamContributions(battleId: number, contextId: string): Promise<{
+        player: string,
+        correctCells: number,
+        removedCells: number,
+        percent: number
+    }[]> {
+        return await httpService.get<any, any>(`/games/battle/contributions/${battleId}/${contextId}`);
+    }
+
     static async createNewBattle(depositPrice: number, creator: Principal): Promise<number> {
         depositPrice *= process.env.TOKEN_DECIMALS;
         let response = await httpService.post<any, any>("/games/battle", {
@@ -69,6 +79,15 @@ export class GameAPI {
         return response;
     }
 
+    static async admitToTeam(contextId: string): Promise<{
+        token: string
+    }> {
+        let response = await httpService.post<any, any>("/games/team/admission", {
+            contextId
+        });
+        return response;
+    }
+
     static async startGame(initialState: [number, number][], battleId: number): Promise<void> {
         await httpService.post<any, any>("/games/start-game", {
             initialState,
@@ -80,30 +99,91 @@ export class GameAPI {
         proofBytes: string,
         publicInputBytes: string
     }> {
-        let proof = await httpService.post<any, {
-            proofBytes: string,
-            publicInputBytes: string
+        let { jobId } = await httpService.post<any, {
+            jobId: string
         }>("/games/generate-proof", {
             initialState,
             solution
         });
-        return proof;
+        let job = await GameAPI.waitForProofJob(jobId);
+        return {
+            proofBytes: job.proof!,
+            publicInputBytes: job.publicValues!
+        };
+    }
+
+    static async getProofJob(jobId: string): Promise<ProofJob> {
+        let job = await httpService.get<any, ProofJob>(`/games/proofs/${jobId}`);
+        return job;
+    }
+
+    static async cancelProofJob(jobId: string): Promise<ProofJob> {
+        let job = await httpService.delete<any, ProofJob>(`/games/proofs/${jobId}`);
+        return job;
+    }
+
+    static async waitForProofJob(jobId: string, intervalMs: number = 5000): Promise<ProofJob> {
+        while (true) {
+            let job = await GameAPI.getProofJob(jobId);
+            if (job.status == "succeeded") {
+                return job;
+            }
+            if (job.status == "failed" || job.status == "cancelled") {
+                throw new Error(job.error ?? `Proof job ${jobId} was ${job.status}`);
+            }
+            await new Promise((resolve) => setTimeout(resolve, intervalMs));
+        }
+    }
+
+    static subscribeBattleEvents(battleId: number, onEvent: (event: BattleEvent) => void): EventSource {
+        let source = new EventSource(`${process.env.API_URL}/games/battle/events/${battleId}`);
+        for (let type of battleEventTypes) {
+            source.addEventListener(type, (message) => onEvent(JSON.parse((message as MessageEvent).data)));
+        }
+        return source;
     }
 
     static async submitBattleProof(battleId: number, solution: number[], isPublic: boolean, playerContributions: {
         player: Principal,
         percent: number
-    }[]): Promise<void> {
+    }[]): Promise<{
+        submissionId: number,
+        proofJobId?: string
+    }> {
         let playerContributionsJson = playerContributions.map((x) => ({
             player: x.player.toString(),
             percent: x.percent
         }));
 
-        await httpService.post<any, any>("/games/battle/solution", {
+        let response = await httpService.post<any, any>("/games/battle/solution", {
             battleId,
             solution,
             public: isPublic,
             playerContributions: playerContributionsJson
         });
+        return response;
+    }
+
+    static async revealHint(battleId: number, contextId: string, position?: number): Promise<{
+        position: number,
+        value: number,
+        hintsLeft: number
+    }> {
+        return await httpService.post<any, any>("/games/battle/hint", {
+            battleId,
+            contextId,
+            position
+        });
+    }
+
+    static async submitTeamSolution(battleId: number, contextId: string): Promise<{
+        submissionId: number,
+        proofJobId?: string
+    }> {
+        let response = await httpService.post<any, any>("/games/battle/team-solution", {
+            battleId,
+            contextId
+        });
+        return response;
     }
 }
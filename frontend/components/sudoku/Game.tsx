@@ -16,8 +16,6 @@ import { CellInfo, SudokuCaller } from '@/api/calimeroService';
 import { getStoragePanic, StorageKey } from '@/utils/storage';
 import { useAuth } from '@nfid/identitykit/react';
 import useSWR from 'swr';
-import _ from 'lodash';
-import { Principal } from '@dfinity/principal';
 import {shortAddress} from "@/utils/chain";
 
 const { Title, Text } = Typography;
@@ -101,6 +99,16 @@ export const SudokuGame = ({
   //   }
   // }, [getCurrentSolutionErr, removedCellsErr, votedSolutionPlayersError]);
 
+  // The team application records the edits and votes of its members under the principal they
+  // joined with, which the server admits the caller with
+  useEffect(() => {
+    if (userPrincipal) {
+      GameAPI.admitToTeam(getStoragePanic(StorageKey.CONTEXT_ID))
+        .then(({ token }) => sudokuCaller.current.join(userPrincipal, getStoragePanic(StorageKey.NODE_NAME), token))
+        .catch((error) => toast.error(JSON.stringify(error)));
+    }
+  }, [userPrincipal]);
+
   useEffect(() => {
     async function handle() {
       if (currentSolution && userPrincipal) {
@@ -144,6 +152,13 @@ export const SudokuGame = ({
     isLoading: isBattleInfoLoading
   } = useSWR(["battle-info", battleId], ([_, battleId]) => GameAPI.getBattleInfo(battleId));
 
+  // How the reward would be split, computed by the server from the team edits
+  const { data: teamContributions } = useSWR(
+    ["team-contributions", battleId],
+    ([_, battleId]) => GameAPI.getTeamContributions(battleId, getStoragePanic(StorageKey.CONTEXT_ID)),
+    { refreshInterval: 5000 }
+  );
+
   useEffect(() => {
     async function handle() {
       try {
@@ -390,18 +405,9 @@ export const SudokuGame = ({
       try {
         if (callUpdate) {
           if (value === '0') {
-            await sudokuCaller.current.removeCell({
-              position: index,
-              editor_address: userPrincipal,
-              editor_name: getStoragePanic(StorageKey.NODE_NAME)
-            });
+            await sudokuCaller.current.removeCell(index);
           } else {
-            await sudokuCaller.current.setCell({
-              position: index,
-              value: parseInt(value),
-              editor_address: userPrincipal,
-              editor_name: getStoragePanic(StorageKey.NODE_NAME)
-            });
+            await sudokuCaller.current.setCell(index, parseInt(value));
           }
         }
         // Direct copy results in interesting set of problems, investigate more!
@@ -511,11 +517,21 @@ export const SudokuGame = ({
 
   /**
    * On Click Hint,
-   * fill the selected cell if its empty or wrong number is filled.
+   * ask the server to reveal the selected cell, or the first empty or wrong one.
+   * Each hint is taken from the team budget and lowers the prize.
    */
-  function onClickHint() {
-    if (cellSelected !== -1) {
-      _fillCell(cellSelected, solvedArray[cellSelected]);
+  async function onClickHint() {
+    try {
+      const hint = await GameAPI.revealHint(
+        battleId,
+        getStoragePanic(StorageKey.CONTEXT_ID),
+        cellSelected !== -1 ? cellSelected : undefined
+      );
+      // The server already wrote the cell in the team context
+      await _fillCell(hint.position, hint.value.toString(), false);
+      toast.info(`Cell ${hint.position} revealed, ${hint.hintsLeft} hints left`);
+    } catch (error) {
+      toast.error(JSON.stringify(error));
     }
   }
 
@@ -642,7 +658,7 @@ export const SudokuGame = ({
                               <div className="w-full bg-gray-600 h-[1px]"></div>
                               {
                                       votedSolutionPlayers[0].map((address) => (
-                                          <Text key={address} className="text-muted">{shortAddress(address, 10)} <Text className="font-semibold">(solved {playerSolvedCells(address)}/{currentSolution?.length})</Text></Text>
+                                          <Text key={address} className="text-muted">{shortAddress(address, 10)} <Text className="font-semibold">(solved {playerSolvedCells(address)}/{currentSolution?.length}, {playerShare(address)}% of the reward)</Text></Text>
                                       ))
                               }
                             </div>
@@ -651,7 +667,7 @@ export const SudokuGame = ({
                               <div className="w-full bg-gray-600 h-[1px]"></div>
                               {
                                 votedSolutionPlayers[1].map((address) => (
-                                    <Text key={address} className="text-muted">{shortAddress(address, 10)} <Text className="font-semibold">(solved {playerSolvedCells(address)}/{currentSolution?.length})</Text></Text>
+                                    <Text key={address} className="text-muted">{shortAddress(address, 10)} <Text className="font-semibold">(solved {playerSolvedCells(address)}/{currentSolution?.length}, {playerShare(address)}% of the reward)</Text></Text>
                                 ))
                               }
                             </div>
@@ -694,19 +710,16 @@ export const SudokuGame = ({
 
   async function handleVoteSolution(isPublic: boolean) {
     if (userPrincipal) {
-      await sudokuCaller.current.voteSolution(isPublic, Principal.fromText(userPrincipal));
+      await sudokuCaller.current.voteSolution(isPublic);
     }
   }
 
   async function handleSubmitSolution(isPublic: boolean) {
     privateProof.current = !isPublic;
     try {
-      const {
-        playerContributions,
-        solution
-      } = getPlayerContributions();
       setSubmittingProof(true);
-      await GameAPI.submitBattleProof(battleId, solution, isPublic, playerContributions);
+      // The server reads the grid, the editors and the votes from the team context
+      await GameAPI.submitTeamSolution(battleId, getStoragePanic(StorageKey.CONTEXT_ID));
       setProofSubmitted(true);
     } catch (error) {
       toast.error(JSON.stringify(error));
@@ -714,19 +727,9 @@ export const SudokuGame = ({
     setSubmittingProof(false);
   }
 
-  function getPlayerContributions() {
-    if (!currentSolution) {
-      throw new Error('No current solution found.');
-    }
-    const solution = currentSolution.sort((a, b) => a.position - b.position).map((x) => x.value);
-    const playerContributions = _.map(_.countBy(currentSolution, (x) => x.editor_address), (counts, player) => ({
-      player: Principal.fromText(player),
-      percent: counts / currentSolution.length
-    }));
-    return {
-      solution,
-      playerContributions
-    }
+  function playerShare(player: string) {
+    const contribution = teamContributions?.find((x) => x.player === player);
+    return Math.round((contribution?.percent ?? 0) * 100);
   }
 
   function playerSolvedCells(player: string) {
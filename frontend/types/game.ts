@@ -8,3 +8,25 @@ export interface GameInfo {
     largestPrizePool: number;
     contractAddress: string;
 }
+
+export interface ProofJob {
+    status: "queued" | "running" | "succeeded" | "failed" | "cancelled";
+    proof?: string;
+    publicValues?: string;
+    error?: string;
+}
+
+export const battleEventTypes = [
+    "battleCreated",
+    "playerJoined",
+    "gameStarted",
+    "proofJobUpdated",
+    "submissionUpdated",
+    "battleFinished",
+] as const;
+
+export type BattleEvent = {
+    type: typeof battleEventTypes[number];
+    battleId: number;
+    [key: string]: any;
+};
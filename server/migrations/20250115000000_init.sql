@@ -0,0 +1,53 @@
+CREATE TABLE IF NOT EXISTS teams (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    battle_id INTEGER,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS team_contexts (
+    context_id TEXT PRIMARY KEY,
+    team_id INTEGER NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+    context_identity TEXT NOT NULL,
+    application_id TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS team_contexts_team_id ON team_contexts(team_id);
+
+-- Mirror of the battles stored in the sudoku canister
+CREATE TABLE IF NOT EXISTS battles (
+    id INTEGER PRIMARY KEY,
+    creator TEXT NOT NULL,
+    deposit_price TEXT NOT NULL,
+    service_fee TEXT NOT NULL,
+    players TEXT NOT NULL,
+    initial_state TEXT,
+    finished INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS proof_jobs (
+    id TEXT PRIMARY KEY,
+    battle_id INTEGER,
+    status TEXT NOT NULL,
+    initial_state TEXT NOT NULL,
+    solution TEXT NOT NULL,
+    proof TEXT,
+    public_values TEXT,
+    error TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS submissions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    battle_id INTEGER NOT NULL,
+    team_id INTEGER REFERENCES teams(id),
+    public INTEGER NOT NULL,
+    proof_job_id TEXT REFERENCES proof_jobs(id),
+    player_contributions TEXT NOT NULL,
+    status TEXT NOT NULL,
+    error TEXT,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS submissions_battle_id ON submissions(battle_id);
@@ -0,0 +1,2 @@
+-- Principal which requested the proof, NULL for the jobs queued before it was recorded
+ALTER TABLE proof_jobs ADD COLUMN owner TEXT;
@@ -0,0 +1,2 @@
+-- Principal which created the team, NULL for the teams created before it was recorded
+ALTER TABLE teams ADD COLUMN creator TEXT;
@@ -1,19 +1,41 @@
-use crate::api::v1::utils::ic_caller::IcCaller;
+use crate::api::v1::auth::ReplayGuard;
+use crate::api::v1::events::EventBus;
+use crate::api::v1::proof_queue::ProofQueue;
+use crate::api::v1::utils::calimero_client::CalimeroClient;
+use crate::api::v1::utils::ic_caller::SudokuContract;
+use crate::config::env_config::Env;
+use crate::storage::sqlite::SqliteStorage;
+use crate::storage::Storage;
 use std::sync::Arc;
 
+/// State shared by all handlers, created once at startup.
 #[derive(Clone)]
-pub struct AppState<'a> {
-    pub sudoku_caller: Arc<IcCaller<'a>>,
+pub struct AppState {
+    pub sudoku_contract: Arc<SudokuContract>,
+    pub calimero: Arc<CalimeroClient>,
+    pub storage: Arc<dyn Storage>,
+    pub proof_queue: Arc<ProofQueue>,
+    pub events: EventBus,
+    pub replay_guard: Arc<ReplayGuard>,
 }
 
-impl<'a> AppState<'a> {
-    // pub fn from_env(config: &Env) -> anyhow::Result<Self> {
-    //     let ic_agent = IcCaller::<'a>::gen_agent(&config.GAME_OWNER_PEM_FILE, &config.RPC_URL)?;
-    //     Ok(Self {
-    //         sudoku_caller: Arc::new(IcCaller::new(
-    //             &config.GAME_CONTRACT,
-    //             &ic_agent,
-    //         )?),
-    //     })
-    // }
-}
+impl AppState {
+    pub async fn from_env(env: &Env) -> anyhow::Result<Self> {
+        let agent = SudokuContract::agent_from_env(env).await?;
+        let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::connect(&env.DATABASE_URL).await?);
+        let events = EventBus::new(1024);
+        Ok(Self {
+            sudoku_contract: Arc::new(SudokuContract::from_env(env, agent)?),
+            calimero: Arc::new(CalimeroClient::new(&env.CALIMERO_NODE_URL)),
+            proof_queue: Arc::new(ProofQueue::new(
+                storage.clone(),
+                events.clone(),
+                env.PROOF_QUEUE_CAPACITY,
+                env.PROOF_JOBS_PER_OWNER,
+            )),
+            storage,
+            events,
+            replay_guard: Arc::new(ReplayGuard::default()),
+        })
+    }
+}
\ No newline at end of file
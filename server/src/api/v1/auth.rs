@@ -0,0 +1,167 @@
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::errors::AppError;
+use crate::api::v1::utils::ic_auth::{request_message, verify_request, DelegationChain};
+use crate::config::env_config::env;
+use anyhow::{anyhow, bail};
+use axum::body::Bytes;
+use axum::extract::{FromRequest, OriginalUri, Request};
+use axum::http::HeaderMap;
+use axum::Json;
+use ic_agent::export::Principal;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::IntoParams;
+
+/// Hex encoded signature of the request message
+pub const SIGNATURE_HEADER: &str = "x-ic-signature";
+/// Milliseconds since the epoch at which the request was signed
+pub const TIMESTAMP_HEADER: &str = "x-ic-timestamp";
+/// Hex encoded DER public key of an identity signing its requests directly
+pub const PUBLIC_KEY_HEADER: &str = "x-ic-public-key";
+/// JSON delegation chain of a session key, as produced by `DelegationChain.toJSON()`
+pub const DELEGATION_HEADER: &str = "x-ic-delegation";
+
+/// Headers of a signed request, only used to document them in the OpenAPI spec.
+/// Either `x-ic-public-key` or `x-ic-delegation` must be set.
+#[derive(IntoParams)]
+#[into_params(parameter_in = Header)]
+#[allow(dead_code)]
+pub struct AuthHeaders {
+    /// Hex encoded signature of the request message
+    #[param(rename = "x-ic-signature")]
+    signature: String,
+    /// Milliseconds since the epoch at which the request was signed
+    #[param(rename = "x-ic-timestamp")]
+    timestamp: u64,
+    /// Hex encoded DER public key of an identity signing its requests directly
+    #[param(rename = "x-ic-public-key")]
+    public_key: Option<String>,
+    /// JSON delegation chain of a session key, as produced by `DelegationChain.toJSON()`
+    #[param(rename = "x-ic-delegation")]
+    delegation: Option<String>,
+}
+
+/// JSON body of a request signed by an IC identity, along with the principal of the signer.
+///
+/// The signature covers the method, the path with its query string, the timestamp and the body, see
+/// [`request_message`]. Internet Identity users sign with their session key and send the
+/// delegation chain leading to it. Use `Authenticated<()>` for requests without a body.
+pub struct Authenticated<T> {
+    pub caller: Principal,
+    pub body: T,
+}
+
+impl<T: DeserializeOwned> FromRequest<AppState> for Authenticated<T> {
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+        let method = req.method().to_string();
+        let uri = req.extensions().get::<OriginalUri>().map_or(req.uri(), |uri| &uri.0);
+        let path = uri.path_and_query().map_or(uri.path(), |path| path.as_str()).to_string();
+        let headers = req.headers().clone();
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(|e| AppError::Validation(e.body_text()))?;
+
+        let caller = verify_headers(state, &headers, &method, &path, &body)
+            .map_err(|e| AppError::Unauthorized(e.to_string()))?;
+        // Requests without a body, such as GET and DELETE, are read as `null`
+        let Json(body) = Json::<T>::from_bytes(if body.is_empty() { b"null" } else { &body })?;
+        Ok(Self { caller, body })
+    }
+}
+
+fn verify_headers(
+    state: &AppState,
+    headers: &HeaderMap,
+    method: &str,
+    path: &str,
+    body: &[u8],
+) -> anyhow::Result<Principal> {
+    let signature = hex::decode(
+        header(headers, SIGNATURE_HEADER)?.ok_or(anyhow!("Missing {} header", SIGNATURE_HEADER))?,
+    )?;
+    let timestamp_ms: u64 = header(headers, TIMESTAMP_HEADER)?
+        .ok_or(anyhow!("Missing {} header", TIMESTAMP_HEADER))?
+        .parse()
+        .map_err(|_| anyhow!("Invalid {} header", TIMESTAMP_HEADER))?;
+    let public_key = header(headers, PUBLIC_KEY_HEADER)?.map(hex::decode).transpose()?;
+    let chain = header(headers, DELEGATION_HEADER)?
+        .map(serde_json::from_str::<DelegationChain>)
+        .transpose()?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    // The timestamp bounds how long the request must be remembered by the replay guard
+    if (now.as_millis() as u64).abs_diff(timestamp_ms) > env().AUTH_MAX_CLOCK_SKEW * 1000 {
+        bail!("Request timestamp is too far from the server time");
+    }
+
+    let message = request_message(method, path, timestamp_ms, body);
+    let caller = verify_request(
+        &message,
+        &signature,
+        public_key.as_deref(),
+        chain.as_ref(),
+        state.sudoku_contract.canister_id(),
+        now.as_nanos() as u64,
+        state.sudoku_contract.agent(),
+    )?;
+    state.replay_guard.check(
+        caller,
+        &message,
+        timestamp_ms + env().AUTH_MAX_CLOCK_SKEW * 1000,
+        now.as_millis() as u64,
+    )?;
+    Ok(caller)
+}
+
+/// Signed messages of the requests accepted while their timestamp is valid, so that a captured
+/// request is only ever handled once. The messages are remembered rather than the signatures,
+/// which ECDSA lets anyone rewrite into other valid ones.
+#[derive(Default)]
+pub struct ReplayGuard {
+    /// (signer, message) -> milliseconds since the epoch after which its timestamp is rejected
+    /// anyway
+    seen: Mutex<HashMap<(Principal, Vec<u8>), u64>>,
+}
+
+impl ReplayGuard {
+    pub fn check(&self, caller: Principal, message: &[u8], expires_at_ms: u64, now_ms: u64) -> anyhow::Result<()> {
+        let mut seen = self.seen.lock().map_err(|_| anyhow!("Replay guard is poisoned"))?;
+        seen.retain(|_, expires_at_ms| *expires_at_ms >= now_ms);
+        if seen.insert((caller, message.to_vec()), expires_at_ms).is_some() {
+            bail!("Request was already handled");
+        }
+        Ok(())
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> anyhow::Result<Option<&'a str>> {
+    headers
+        .get(name)
+        .map(|value| value.to_str())
+        .transpose()
+        .map_err(|_| anyhow!("Invalid {} header", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::v1::auth::ReplayGuard;
+    use ic_agent::export::Principal;
+
+    #[test]
+    fn test_replay_guard() {
+        let alice = Principal::self_authenticating(b"alice");
+        let bob = Principal::self_authenticating(b"bob");
+        let guard = ReplayGuard::default();
+        guard.check(alice, b"msg1", 100, 0).unwrap();
+        assert!(guard.check(alice, b"msg1", 100, 50).is_err());
+        guard.check(alice, b"msg2", 200, 50).unwrap();
+        // the same request signed by someone else is another request
+        guard.check(bob, b"msg1", 100, 50).unwrap();
+        // expired messages are forgotten, their timestamp is rejected before reaching the guard
+        guard.check(alice, b"msg1", 300, 150).unwrap();
+    }
+}
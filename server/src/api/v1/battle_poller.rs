@@ -0,0 +1,33 @@
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::controllers::games::battle::{mirror_battle, sync_battle};
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+
+/// Periodically refreshes the battle mirrors from the canister, so changes made without going
+/// through the server are still published to the event subscribers.
+pub fn start_battle_poller(state: AppState, period: Duration) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(e) = poll_battles(&state).await {
+                eprintln!("Fail to poll battles: {:?}", e);
+            }
+        }
+    });
+}
+
+async fn poll_battles(state: &AppState) -> anyhow::Result<()> {
+    for battle in state.storage.list_unfinished_battles().await? {
+        sync_battle(state, battle.id).await?;
+    }
+
+    // Battles are numbered in creation order, the first missing id ends the new ones
+    let mut battle_id = state.storage.last_battle_id().await?.map_or(0, |id| id + 1);
+    while let Ok(game) = state.sudoku_contract.get_battle_info(battle_id).await {
+        mirror_battle(state, battle_id, &game).await?;
+        battle_id += 1;
+    }
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,25 @@
+use crate::api::v1::app_state::AppState;
+use axum::extract::{Path, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use tokio_stream::{Stream, StreamExt};
+
+/// Streams the events of a battle as Server-Sent Events, named after the event type.
+#[utoipa::path(
+    get,
+    path = "/api/v1/games/battle/events/{id}",
+    tag = "battle",
+    params(("id" = usize, Path, description = "Battle id")),
+    responses(
+        (status = 200, description = "JSON battle events, the SSE event name is their `type`", content_type = "text/event-stream"),
+    )
+)]
+pub async fn get_battle_events(
+    State(state): State<AppState>,
+    Path(id): Path<usize>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let events = state
+        .events
+        .subscribe(id)
+        .map(|event| Event::default().event(event.name()).json_data(&event));
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
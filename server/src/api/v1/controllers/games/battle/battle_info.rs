@@ -1,19 +1,28 @@
-use axum::extract::Path;
-use crate::api::v1::errors::AppError;
-use crate::api::v1::utils::ic_caller::SudokuContract;
-use crate::config::env_config::env;
+use axum::extract::{Path, State};
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::controllers::games::battle::sync_battle;
+use crate::api::v1::errors::{AppError, ErrorBody};
 use axum::Json;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 pub struct BattleInfoRes(sudoku::game::SudokuGame);
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/games/battle/info/{id}",
+    tag = "battle",
+    params(("id" = usize, Path, description = "Battle id")),
+    responses(
+        (status = 200, body = BattleInfoRes),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
 pub async fn get_battle_info(
+    State(state): State<AppState>,
     Path(id): Path<usize>,
 ) -> Result<Json<BattleInfoRes>, AppError> {
-    let env = env();
-    let agent = SudokuContract::agent_from_env(env).await?;
-    let sudoku_contract = SudokuContract::from_env(env, &agent)?;
-    let res = sudoku_contract.get_battle_info(id).await?;
+    let res = sync_battle(&state, id).await?;
     Ok(Json(BattleInfoRes(res)))
 }
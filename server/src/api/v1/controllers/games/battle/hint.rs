@@ -0,0 +1,97 @@
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::auth::{AuthHeaders, Authenticated};
+use crate::api::v1::controllers::games::battle::submit_solution::{battle_of_player, initial_state, team_of_battle};
+use crate::api::v1::errors::{AppError, ErrorBody};
+use crate::api::v1::utils::team_state::{reveal_cell, team_members, TeamState};
+use axum::extract::State;
+use axum::Json;
+use ic_agent::export::Principal;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RevealHintReq {
+    battle_id: usize,
+    /// Calimero context of the team, as returned when the team was created
+    context_id: String,
+    /// Cell to reveal, defaults to the first cell of the grid which is empty or wrong
+    #[serde(default)]
+    position: Option<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RevealHintRes {
+    position: u8,
+    value: u8,
+    /// Hints the team can still take in the battle
+    hints_left: u32,
+}
+
+/// Writes the value of a cell into the grid of the caller's team, taken from a solution of the
+/// puzzle which keeps the cells the team filled consistently. The hint is taken
+/// from the team's budget on the sudoku canister before it is revealed, and given back when the
+/// reveal fails. Each hint lowers the prize of any solution its team's members contribute to.
+/// The caller must have joined the battle and be a solver of the team.
+#[utoipa::path(
+    post,
+    path = "/api/v1/games/battle/hint",
+    tag = "battle",
+    params(AuthHeaders),
+    request_body = RevealHintReq,
+    responses(
+        (status = 200, body = RevealHintRes),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
+pub async fn reveal_hint(
+    State(state): State<AppState>,
+    Authenticated {
+        caller,
+        body: RevealHintReq {
+            battle_id,
+            context_id,
+            position,
+        },
+    }: Authenticated<RevealHintReq>,
+) -> Result<Json<RevealHintRes>, AppError> {
+    let team = team_of_battle(&state, battle_id, &context_id).await?;
+    let battle = battle_of_player(&state, battle_id, caller).await?;
+    if !battle.players.contains(&caller) {
+        return Err(AppError::Forbidden(format!("{} has not joined the battle", caller)));
+    }
+    let members = team_members(&state.calimero, &team.context).await?;
+    let solver = members
+        .iter()
+        .any(|member| member.address == caller.to_text() && member.role != "Spectator");
+    if !solver {
+        return Err(AppError::Forbidden(format!("{} is not a solver of team {}", caller, team.id)));
+    }
+    // Every member sees the revealed cell, so all of them are charged for it
+    let players: Vec<Principal> = members
+        .iter()
+        .filter_map(|member| Principal::from_text(&member.address).ok())
+        .collect();
+
+    let initial_state = initial_state(&state, &battle).await?;
+    let team_state = TeamState::read(&state.calimero, &team.context).await?;
+    let (position, value) = team_state.hint(&initial_state, position)?;
+    // Charged first, so a hint is never revealed for free
+    let hints_left = state
+        .sudoku_contract
+        .use_hint(battle_id, context_id.clone(), players.clone())
+        .await?;
+    if let Err(e) = reveal_cell(&state.calimero, &team.context, position, value).await {
+        if let Err(refund) = state.sudoku_contract.refund_hint(battle_id, context_id, players).await {
+            eprintln!("Fail to refund hint: {:?}", refund);
+        }
+        return Err(e.into());
+    }
+
+    Ok(Json(RevealHintRes {
+        position,
+        value,
+        hints_left,
+    }))
+}
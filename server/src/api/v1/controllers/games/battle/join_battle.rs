@@ -1,43 +1,61 @@
-use crate::api::v1::errors::AppError;
-use crate::api::v1::utils::ic_caller::SudokuContract;
-use crate::config::env_config::env;
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::auth::{AuthHeaders, Authenticated};
+use crate::api::v1::controllers::games::battle::sync_battle;
+use crate::api::v1::errors::{AppError, ErrorBody};
+use axum::extract::State;
 use axum::Json;
-use ic_agent::export::Principal;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct JoinBattleReq {
     battle_id: usize,
-    player: Principal,
 }
 
-pub async fn join_battle(Json(game): Json<JoinBattleReq>) -> Result<Json<()>, AppError> {
-    let env = env();
-    let agent = SudokuContract::agent_from_env(env).await?;
-    let sudoku_contract = SudokuContract::from_env(env, &agent)?;
-    sudoku_contract.join_battle(game.battle_id, game.player).await?;
+/// Joins a battle as the caller.
+#[utoipa::path(
+    post,
+    path = "/api/v1/games/battle/join",
+    tag = "battle",
+    params(AuthHeaders),
+    request_body = JoinBattleReq,
+    responses(
+        (status = 200, description = "Joined the battle"),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
+pub async fn join_battle(
+    State(state): State<AppState>,
+    Authenticated { caller, body: game }: Authenticated<JoinBattleReq>,
+) -> Result<Json<()>, AppError> {
+    state.sudoku_contract.join_battle(game.battle_id, caller).await?;
+    sync_battle(&state, game.battle_id).await?;
     Ok(Json(()))
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::api::v1::app_state::AppState;
+    use crate::api::v1::auth::Authenticated;
     use crate::api::v1::controllers::games::battle::join_battle::{join_battle, JoinBattleReq};
     use crate::config::config_app;
-    use axum::Json;
+    use crate::config::env_config::env;
+    use axum::extract::State;
     use ic_agent::export::Principal;
     use std::str::FromStr;
 
     #[tokio::test]
     async fn test_start_game() {
         config_app().await;
-        let res = join_battle(Json(JoinBattleReq {
-            battle_id: 4,
-            player: Principal::from_str(
+        let state = AppState::from_env(env()).await.unwrap();
+        let res = join_battle(State(state), Authenticated {
+            caller: Principal::from_str(
                 "pnklf-ojyec-2l2al-nuxfq-3vss6-udii2-2txam-wusja-usc54-n5uos-gae",
             )
             .unwrap(),
-        }))
+            body: JoinBattleReq { battle_id: 4 },
+        })
         .await
         .unwrap();
         dbg!(res.0);
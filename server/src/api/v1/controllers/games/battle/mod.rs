@@ -1,46 +1,95 @@
 pub mod join_battle;
 pub mod battle_info;
 pub mod submit_solution;
+pub mod battle_events;
+pub mod team_contributions;
+pub mod hint;
 
-use crate::api::v1::errors::AppError;
-use crate::api::v1::utils::ic_caller::SudokuContract;
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::auth::{AuthHeaders, Authenticated};
+use crate::api::v1::errors::{AppError, ErrorBody};
+use crate::api::v1::events::battle_events;
 use crate::config::env_config::env;
+use crate::storage::BattleRecord;
+use axum::extract::State;
 use axum::Json;
-use ic_agent::export::Principal;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateNewBattleReq {
     deposit_price: u128,
-    creator: Principal,
 }
 
+/// Creates a battle owned by the caller.
+#[utoipa::path(
+    post,
+    path = "/api/v1/games/battle",
+    tag = "battle",
+    params(AuthHeaders),
+    request_body = CreateNewBattleReq,
+    responses(
+        (status = 200, description = "Id of the new battle", body = usize),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
 pub async fn create_new_battle(
-    Json(battle_info): Json<CreateNewBattleReq>,
+    State(state): State<AppState>,
+    Authenticated { caller, body: battle_info }: Authenticated<CreateNewBattleReq>,
 ) -> Result<Json<usize>, AppError> {
-    let env = env();
-    let agent = SudokuContract::agent_from_env(env).await?;
-    let sudoku_contract = SudokuContract::from_env(env, &agent)?;
-    let battle_id = sudoku_contract.create_new_battle(battle_info.deposit_price, env.SERVICE_FEE, battle_info.creator).await?;
+    let battle_id = state.sudoku_contract.create_new_battle(battle_info.deposit_price, env().SERVICE_FEE, caller, env().HINT_BUDGET, env().HINT_PENALTY)
+        .await?;
+    sync_battle(&state, battle_id).await?;
     Ok(Json(battle_id))
 }
 
+/// Reads a battle from the canister and refreshes its mirror in storage.
+pub(crate) async fn sync_battle(
+    state: &AppState,
+    battle_id: usize,
+) -> anyhow::Result<sudoku::game::SudokuGame> {
+    let game = state.sudoku_contract.get_battle_info(battle_id).await?;
+    mirror_battle(state, battle_id, &game).await?;
+    Ok(game)
+}
+
+/// Stores a battle read from the canister and publishes what changed since the last mirror.
+pub(crate) async fn mirror_battle(
+    state: &AppState,
+    battle_id: usize,
+    game: &sudoku::game::SudokuGame,
+) -> anyhow::Result<()> {
+    let old = state.storage.get_battle(battle_id).await?;
+    state
+        .storage
+        .upsert_battle(&BattleRecord::from_game(battle_id, game))
+        .await?;
+    for event in battle_events(old.as_ref(), battle_id, game) {
+        state.events.publish(event);
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
     use crate::config::config_app;
-    use axum::Json;
+    use crate::config::env_config::env;
+    use axum::extract::State;
     use ic_agent::export::Principal;
+    use crate::api::v1::app_state::AppState;
+    use crate::api::v1::auth::Authenticated;
     use crate::api::v1::controllers::games::battle::{create_new_battle, CreateNewBattleReq};
 
     #[tokio::test]
     async fn test_create_new_battle() {
         config_app().await;
-        let res = create_new_battle(Json(CreateNewBattleReq {
-            deposit_price: 1,
-            creator: Principal::from_str("pnklf-ojyec-2l2al-nuxfq-3vss6-udii2-2txam-wusja-usc54-n5uos-gae").unwrap(),
-        }))
+        let state = AppState::from_env(env()).await.unwrap();
+        let res = create_new_battle(State(state), Authenticated {
+            caller: Principal::from_str("pnklf-ojyec-2l2al-nuxfq-3vss6-udii2-2txam-wusja-usc54-n5uos-gae").unwrap(),
+            body: CreateNewBattleReq { deposit_price: 1 },
+        })
             .await
             .unwrap();
         dbg!(res.0);
@@ -1,71 +1,361 @@
-use crate::api::v1::errors::AppError;
-use crate::api::v1::utils::ic_caller::SudokuContract;
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::auth::{AuthHeaders, Authenticated};
+use crate::api::v1::controllers::games::battle::sync_battle;
+use crate::api::v1::errors::{AppError, ErrorBody};
+use crate::api::v1::events::BattleEvent;
+use crate::api::v1::utils::team_state::{team_members, TeamMember, TeamState};
 use crate::config::env_config::env;
-use crate::games::sudoku::SudokuGame;
-use crate::games::Game;
+use crate::storage::{now, BattleRecord, ProofJob, ProofJobStatus, Submission, SubmissionStatus, Team};
+use anyhow::anyhow;
+use axum::extract::State;
 use axum::Json;
+use ic_agent::export::Principal;
 use serde::{Deserialize, Serialize};
 use sudoku::game::{GameSolution, PlayerContribution};
+use utoipa::ToSchema;
 use verifier::types::SP1Proof;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// How far from 1 the contribution percents may add up to, for rounding
+const PERCENT_TOLERANCE: f32 = 1e-3;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SubmitSolutionReq {
     battle_id: usize,
     solution: Vec<u8>,
     public: bool,
     player_contributions: Vec<PlayerContribution>,
+    /// Team submitting the solution, as returned when the team was created
+    #[serde(default)]
+    team_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitTeamSolutionReq {
+    battle_id: usize,
+    /// Calimero context of the team, as returned when the team was created
+    context_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitSolutionRes {
+    submission_id: i64,
+    /// Proof job of a private solution, which is submitted once the job succeeds
+    proof_job_id: Option<String>,
 }
 
+/// Submits a solution, on behalf of the caller's team when `teamId` is set. The caller and
+/// every contributor must have joined the battle, the caller must be a member of the team and
+/// the percents of the contributors must add up to 1.
+#[utoipa::path(
+    post,
+    path = "/api/v1/games/battle/solution",
+    tag = "battle",
+    params(AuthHeaders),
+    request_body = SubmitSolutionReq,
+    responses(
+        (status = 200, body = SubmitSolutionRes),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
 pub async fn submit_battle_solution(
-    Json(SubmitSolutionReq {
+    State(state): State<AppState>,
+    Authenticated {
+        caller,
+        body: SubmitSolutionReq {
+            battle_id,
+            solution,
+            public,
+            player_contributions,
+            team_id,
+        },
+    }: Authenticated<SubmitSolutionReq>,
+) -> Result<Json<SubmitSolutionRes>, AppError> {
+    let team = match team_id {
+        Some(team_id) => {
+            let team = state
+                .storage
+                .get_team(team_id)
+                .await?
+                .ok_or(AppError::NotFound(format!("Team {}", team_id)))?;
+            let team = team_playing(team, battle_id)?;
+            member_of_team(&state, &team, caller).await?;
+            Some(team)
+        }
+        None => None,
+    };
+    let battle = battle_of_player(&state, battle_id, caller).await?;
+    submit(&state, caller, battle, solution, public, player_contributions, team).await
+}
+
+/// Submits the grid of a team as it is in its Calimero context: the filled cells form the
+/// solution, their editors share the reward and the team votes decide whether the solution is
+/// public. The caller must have joined the battle, be a member of the team and have filled some
+/// of the cells.
+#[utoipa::path(
+    post,
+    path = "/api/v1/games/battle/team-solution",
+    tag = "battle",
+    params(AuthHeaders),
+    request_body = SubmitTeamSolutionReq,
+    responses(
+        (status = 200, body = SubmitSolutionRes),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
+pub async fn submit_team_solution(
+    State(state): State<AppState>,
+    Authenticated {
+        caller,
+        body: SubmitTeamSolutionReq {
+            battle_id,
+            context_id,
+        },
+    }: Authenticated<SubmitTeamSolutionReq>,
+) -> Result<Json<SubmitSolutionRes>, AppError> {
+    let team = team_of_battle(&state, battle_id, &context_id).await?;
+    member_of_team(&state, &team, caller).await?;
+    let battle = battle_of_player(&state, battle_id, caller).await?;
+    let initial_state = initial_state(&state, &battle).await?;
+    let team_state = TeamState::read(&state.calimero, &team.context).await?;
+    let public = team_state.voted_public(&initial_state)?;
+    let solution = team_state.solution(&initial_state)?;
+    let player_contributions =
+        team_state.contributions(&initial_state, env().CONTRIBUTION_REMOVAL_WEIGHT)?;
+    submit(&state, caller, battle, solution, public, player_contributions, Some(team)).await
+}
+
+/// The team playing `battle_id` in the Calimero context `context_id`.
+pub(crate) async fn team_of_battle(
+    state: &AppState,
+    battle_id: usize,
+    context_id: &str,
+) -> Result<Team, AppError> {
+    let team = state
+        .storage
+        .get_team_by_context(context_id)
+        .await?
+        .ok_or(AppError::NotFound(format!("Team of context {}", context_id)))?;
+    team_playing(team, battle_id)
+}
+
+fn team_playing(team: Team, battle_id: usize) -> Result<Team, AppError> {
+    if team.battle_id != Some(battle_id) {
+        return Err(AppError::Validation(format!(
+            "Team {} does not play battle {}",
+            team.id, battle_id
+        )));
+    }
+    Ok(team)
+}
+
+/// The member of a team context which `caller` plays as.
+pub(crate) async fn member_of_team(state: &AppState, team: &Team, caller: Principal) -> Result<TeamMember, AppError> {
+    team_members(&state.calimero, &team.context)
+        .await?
+        .into_iter()
+        .find(|member| member.address == caller.to_text())
+        .ok_or(AppError::Forbidden(format!("{} is not a member of team {}", caller, team.id)))
+}
+
+/// Mirror of a battle, refreshed when it misses `caller`: players never leave a battle.
+pub(crate) async fn battle_of_player(
+    state: &AppState,
+    battle_id: usize,
+    caller: Principal,
+) -> anyhow::Result<BattleRecord> {
+    Ok(match state.storage.get_battle(battle_id).await? {
+        Some(battle) if battle.players.contains(&caller) => battle,
+        _ => BattleRecord::from_game(battle_id, &sync_battle(state, battle_id).await?),
+    })
+}
+
+/// The initial state never changes once the game started, so the mirror is enough.
+pub(crate) async fn initial_state(state: &AppState, battle: &BattleRecord) -> Result<Vec<(u8, u8)>, AppError> {
+    match &battle.initial_state {
+        Some(initial_state) => Ok(initial_state.clone()),
+        None => sync_battle(state, battle.id)
+            .await?
+            .initial_state
+            .ok_or(AppError::Conflict(format!("Battle {} has not started", battle.id))),
+    }
+}
+
+async fn submit(
+    state: &AppState,
+    caller: Principal,
+    battle: BattleRecord,
+    solution: Vec<u8>,
+    public: bool,
+    player_contributions: Vec<PlayerContribution>,
+    team: Option<Team>,
+) -> Result<Json<SubmitSolutionRes>, AppError> {
+    let battle_id = battle.id;
+    check_contributions(&battle.players, caller, &player_contributions)?;
+    let team_id = team.as_ref().map(|team| team.id);
+    // The context identifies the team on the canister for its hints
+    let team = team.map(|team| team.context.context_id);
+
+    let mut submission = Submission {
+        id: 0,
         battle_id,
-        solution,
+        team_id,
         public,
-        player_contributions,
-    }): Json<SubmitSolutionReq>,
-) -> Result<Json<()>, AppError> {
-    let env = env();
-    let agent = SudokuContract::agent_from_env(env).await?;
-    let sudoku_contract = SudokuContract::from_env(env, &agent)?;
+        proof_job_id: None,
+        player_contributions: player_contributions.clone(),
+        status: SubmissionStatus::Pending,
+        error: None,
+        created_at: now(),
+    };
 
     if public {
-        sudoku_contract
+        let res = state
+            .sudoku_contract
             .submit_solution(
                 battle_id,
                 GameSolution::Public(solution),
                 player_contributions,
+                team,
             )
-            .await?;
-    } else {
-        let initial_state = sudoku_contract
-            .get_battle_info(battle_id)
-            .await?
-            .initial_state
-            .unwrap();
+            .await;
+        match &res {
+            Ok(()) => submission.status = SubmissionStatus::Accepted,
+            Err(e) => {
+                submission.status = SubmissionStatus::Rejected;
+                submission.error = Some(e.to_string());
+            }
+        }
+        submission.id = state.storage.insert_submission(&submission).await?;
+        publish_submission(state, &submission);
+        res?;
+        sync_battle(state, battle_id).await?;
+        return Ok(Json(SubmitSolutionRes {
+            submission_id: submission.id,
+            proof_job_id: None,
+        }));
+    }
 
-        let game = SudokuGame {
-            initial_state,
-            solution,
-        };
-        let proof = game.generate_proof().await?;
-        sudoku_contract
-            .submit_solution(
-                battle_id,
-                GameSolution::Private(verifier::types::SP1ProofWithPublicValues {
-                    proof: SP1Proof::Groth16(hex::encode(proof.bytes())),
-                    public_values: hex::encode(proof.public_values.to_vec()),
-                }),
-                player_contributions,
-            )
-            .await?;
+    let initial_state = initial_state(state, &battle).await?;
+
+    // The submission is stored before the job is queued so the worker always finds it
+    let job = ProofJob::new(Some(battle_id), caller, initial_state, solution);
+    state.proof_queue.insert(&job).await?;
+    submission.proof_job_id = Some(job.id.clone());
+    submission.id = state.storage.insert_submission(&submission).await?;
+    publish_submission(state, &submission);
+    state.proof_queue.publish(&job).await?;
+    state.proof_queue.enqueue(job.id.clone())?;
+
+    Ok(Json(SubmitSolutionRes {
+        submission_id: submission.id,
+        proof_job_id: Some(job.id),
+    }))
+}
+
+fn check_contributions(
+    players: &[Principal],
+    caller: Principal,
+    player_contributions: &[PlayerContribution],
+) -> Result<(), AppError> {
+    if !players.contains(&caller) {
+        return Err(AppError::Forbidden(format!("{} has not joined the battle", caller)));
+    }
+    if !player_contributions.iter().any(|c| c.player == caller) {
+        return Err(AppError::Forbidden(format!("{} is not a contributor of the solution", caller)));
+    }
+    if let Some(c) = player_contributions.iter().find(|c| !players.contains(&c.player)) {
+        return Err(AppError::Validation(format!("{} has not joined the battle", c.player)));
+    }
+    let total: f32 = player_contributions.iter().map(|c| c.percent).sum();
+    let in_range = player_contributions.iter().all(|c| (0.0..=1.0).contains(&c.percent));
+    if !in_range || (total - 1.0).abs() > PERCENT_TOLERANCE {
+        return Err(AppError::Validation(format!(
+            "Contribution percents must be between 0 and 1 and add up to 1, not {}",
+            total
+        )));
     }
-    Ok(Json(()))
+    Ok(())
+}
+
+/// Sends the pending submission waiting for a finished proof job, or rejects it if the job
+/// did not succeed.
+pub(crate) async fn finish_private_submission(state: &AppState, job: &ProofJob) -> anyhow::Result<()> {
+    let Some(mut submission) = state.storage.get_submission_by_proof_job(&job.id).await? else {
+        return Ok(());
+    };
+    if submission.status != SubmissionStatus::Pending {
+        return Ok(());
+    }
+
+    let res = match job.status {
+        ProofJobStatus::Queued | ProofJobStatus::Running => return Ok(()),
+        ProofJobStatus::Succeeded => submit_proof(state, &submission, job).await,
+        ProofJobStatus::Failed => Err(anyhow!(
+            "Proof generation failed: {}",
+            job.error.as_deref().unwrap_or_default()
+        )),
+        ProofJobStatus::Cancelled => Err(anyhow!("Proof job {} was cancelled", job.id)),
+    };
+    match &res {
+        Ok(()) => submission.status = SubmissionStatus::Accepted,
+        Err(e) => {
+            submission.status = SubmissionStatus::Rejected;
+            submission.error = Some(e.to_string());
+        }
+    }
+    state.storage.update_submission(&submission).await?;
+    publish_submission(state, &submission);
+    res?;
+    sync_battle(state, submission.battle_id).await?;
+    Ok(())
+}
+
+fn publish_submission(state: &AppState, submission: &Submission) {
+    state.events.publish(BattleEvent::SubmissionUpdated {
+        battle_id: submission.battle_id,
+        submission_id: submission.id,
+        status: submission.status,
+        error: submission.error.clone(),
+    });
+}
+
+async fn submit_proof(state: &AppState, submission: &Submission, job: &ProofJob) -> anyhow::Result<()> {
+    let sudoku_contract = &state.sudoku_contract;
+    let proof = job.proof.clone().ok_or(anyhow!("Proof job {} has no proof", job.id))?;
+    let public_values = job
+        .public_values
+        .clone()
+        .ok_or(anyhow!("Proof job {} has no public values", job.id))?;
+
+    // Run the same check as the verifier canister so a rejected proof never reaches the chain
+    let vk = sudoku_contract.get_global_state().await?.vk;
+    let team = match submission.team_id {
+        Some(team_id) => state.storage.get_team(team_id).await?.map(|team| team.context.context_id),
+        None => None,
+    };
+    verifier::verify_sp1_groth16(&hex::decode(&proof)?, &hex::decode(&public_values)?, &hex::decode(&vk)?)
+        .map_err(|e| anyhow!("Proof would be rejected by the verifier canister: {}", e))?;
+
+    sudoku_contract
+        .submit_solution(
+            submission.battle_id,
+            GameSolution::Private(verifier::types::SP1ProofWithPublicValues {
+                proof: SP1Proof::Groth16(proof),
+                public_values,
+            }),
+            submission.player_contributions.clone(),
+            team,
+        )
+        .await
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::api::v1::controllers::games::battle::submit_solution::SubmitSolutionReq;
+    use crate::api::v1::controllers::games::battle::submit_solution::{check_contributions, SubmitSolutionReq};
+    use ic_agent::export::Principal;
+    use sudoku::game::PlayerContribution;
 
     #[test]
     fn test() {
@@ -73,4 +363,23 @@ mod tests {
         let parsed = serde_json::from_str::<SubmitSolutionReq>(g).unwrap();
         dbg!(parsed);
     }
+
+    #[test]
+    fn test_check_contributions() {
+        let alice = Principal::self_authenticating(b"alice");
+        let bob = Principal::self_authenticating(b"bob");
+        let eve = Principal::self_authenticating(b"eve");
+        let contribution = |player, percent| PlayerContribution { player, percent };
+
+        let players = [alice, bob];
+        assert!(check_contributions(&players, alice, &[contribution(alice, 0.7), contribution(bob, 0.3)]).is_ok());
+        // the caller must take part in the solution
+        assert!(check_contributions(&players, alice, &[contribution(bob, 1.0)]).is_err());
+        // everyone rewarded must have joined the battle
+        assert!(check_contributions(&players, alice, &[contribution(alice, 0.5), contribution(eve, 0.5)]).is_err());
+        assert!(check_contributions(&players, eve, &[contribution(eve, 1.0)]).is_err());
+        // the whole reward is shared
+        assert!(check_contributions(&players, alice, &[contribution(alice, 0.5), contribution(bob, 0.4)]).is_err());
+        assert!(check_contributions(&players, alice, &[contribution(alice, 1.5), contribution(bob, -0.5)]).is_err());
+    }
 }
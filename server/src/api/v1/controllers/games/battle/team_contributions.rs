@@ -0,0 +1,47 @@
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::auth::{AuthHeaders, Authenticated};
+use crate::api::v1::controllers::games::battle::submit_solution::{initial_state, member_of_team, team_of_battle};
+use crate::api::v1::controllers::games::battle::sync_battle;
+use crate::api::v1::errors::{AppError, ErrorBody};
+use crate::api::v1::utils::team_state::{MemberContribution, TeamState};
+use crate::config::env_config::env;
+use crate::storage::BattleRecord;
+use axum::extract::{Path, State};
+use axum::Json;
+
+/// Previews how the reward of a team would be split if it submitted its grid now, which must be
+/// solved. Each cell counts for its last editor, emptying a wrong value of a teammate counts for
+/// a fraction of a cell. The caller must be a member of the team: other teams cannot see its
+/// grid.
+#[utoipa::path(
+    get,
+    path = "/api/v1/games/battle/contributions/{id}/{context_id}",
+    tag = "battle",
+    params(
+        ("id" = usize, Path, description = "Battle id"),
+        ("context_id" = String, Path, description = "Calimero context of the team"),
+        AuthHeaders,
+    ),
+    responses(
+        (status = 200, body = Vec<MemberContribution>),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
+pub async fn get_team_contributions(
+    State(state): State<AppState>,
+    Path((battle_id, context_id)): Path<(usize, String)>,
+    Authenticated { caller, .. }: Authenticated<()>,
+) -> Result<Json<Vec<MemberContribution>>, AppError> {
+    let team = team_of_battle(&state, battle_id, &context_id).await?;
+    member_of_team(&state, &team, caller).await?;
+    let battle = match state.storage.get_battle(battle_id).await? {
+        Some(battle) => battle,
+        None => BattleRecord::from_game(battle_id, &sync_battle(&state, battle_id).await?),
+    };
+    let initial_state = initial_state(&state, &battle).await?;
+    let team_state = TeamState::read(&state.calimero, &team.context).await?;
+    Ok(Json(team_state.member_contributions(
+        &initial_state,
+        env().CONTRIBUTION_REMOVAL_WEIGHT,
+    )?))
+}
@@ -1,9 +1,10 @@
-use crate::api::v1::errors::AppError;
+use crate::api::v1::errors::{AppError, ErrorBody};
 use crate::config::env_config::env;
 use axum::Json;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GameInfoRes {
     pub game_contract: String,
@@ -11,6 +12,15 @@ pub struct GameInfoRes {
     pub application_id: String,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/games/info",
+    tag = "games",
+    responses(
+        (status = 200, body = GameInfoRes),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
 pub async fn get_game_info() -> Result<Json<GameInfoRes>, AppError> {
     let env = env();
     Ok(Json(GameInfoRes {
@@ -1,50 +1,73 @@
-use crate::api::v1::errors::AppError;
-use crate::games::sudoku::SudokuGame;
-use crate::games::Game;
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::auth::{AuthHeaders, Authenticated};
+use crate::api::v1::controllers::games::check_sudoku_input;
+use crate::api::v1::errors::{AppError, ErrorBody};
+use crate::storage::ProofJob;
+use axum::extract::State;
 use axum::Json;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GenerateProofReq {
+    #[schema(value_type = Vec<Vec<u8>>)]
     initial_state: Vec<(u8, u8)>,
     solution: Vec<u8>
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GenerateProofRes {
-    proof_bytes: String,
-    public_input_bytes: String
+    /// Poll `/games/proofs/{jobId}` for the proof
+    job_id: String,
 }
 
+/// Queues the proving of a solution. Refused with 429 while the queue is full or the caller
+/// already has as many unfinished jobs as allowed.
+#[utoipa::path(
+    post,
+    path = "/api/v1/games/generate-proof",
+    tag = "proofs",
+    params(AuthHeaders),
+    request_body = GenerateProofReq,
+    responses(
+        (status = 200, body = GenerateProofRes),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
 pub async fn generate_proof(
-    Json(game): Json<GenerateProofReq>,
+    State(state): State<AppState>,
+    Authenticated { caller, body: game }: Authenticated<GenerateProofReq>,
 ) -> Result<Json<GenerateProofRes>, AppError> {
-    let game = SudokuGame {
-        initial_state: game.initial_state,
-        solution: game.solution
-    };
-    let proof = game.generate_proof().await?;
-    Ok(Json(GenerateProofRes {
-        proof_bytes: hex::encode(proof.bytes()),
-        public_input_bytes: hex::encode(proof.public_values.to_vec()),
-    }))
+    check_sudoku_input(&game.initial_state, Some(&game.solution))?;
+    let job = ProofJob::new(None, caller, game.initial_state, game.solution);
+    let job_id = job.id.clone();
+    state.proof_queue.submit(job).await?;
+    Ok(Json(GenerateProofRes { job_id }))
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::api::v1::app_state::AppState;
+    use crate::api::v1::auth::Authenticated;
     use crate::api::v1::controllers::games::generate_proof::{generate_proof, GenerateProofReq};
     use crate::config::config_app;
-    use axum::Json;
+    use crate::config::env_config::env;
+    use axum::extract::State;
+    use ic_agent::export::Principal;
 
     #[tokio::test]
     async fn test_generate_proof() {
         config_app().await;
-        let res = generate_proof(Json(GenerateProofReq {
-            initial_state: vec![],
-            solution: vec![],
-        })).await.unwrap();
+        let state = AppState::from_env(env()).await.unwrap();
+        let res = generate_proof(State(state), Authenticated {
+            caller: Principal::anonymous(),
+            body: GenerateProofReq {
+                initial_state: vec![],
+                solution: vec![5; 81],
+            },
+        }).await.unwrap();
         dbg!(res.0);
     }
 }
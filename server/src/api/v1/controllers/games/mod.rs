@@ -2,4 +2,60 @@ pub mod generate_proof;
 pub mod game_info;
 pub mod start_game;
 pub mod team;
-pub mod battle;
\ No newline at end of file
+pub mod battle;
+pub mod receipt;
+pub mod proofs;
+
+use crate::api::v1::errors::AppError;
+
+/// Checks the shape of a sudoku grid sent by a client: `initial_state` holds `(cell, value)`
+/// pairs and `solution` the values of the remaining cells. Whether the grid is solved is left to
+/// the game.
+pub(crate) fn check_sudoku_input(
+    initial_state: &[(u8, u8)],
+    solution: Option<&[u8]>,
+) -> Result<(), AppError> {
+    let mut filled = [false; 81];
+    for &(cell, value) in initial_state {
+        if cell >= 81 {
+            return Err(AppError::Validation(format!("cell {} is outside of the grid", cell)));
+        }
+        if filled[cell as usize] {
+            return Err(AppError::Validation(format!("cell {} is given twice", cell)));
+        }
+        if !(1..=9).contains(&value) {
+            return Err(AppError::Validation(format!("value of cell {} must be between 1 and 9", cell)));
+        }
+        filled[cell as usize] = true;
+    }
+
+    if let Some(solution) = solution {
+        if initial_state.len() + solution.len() != 81 {
+            return Err(AppError::Validation(format!(
+                "solution must fill the {} empty cells, got {} values",
+                81 - initial_state.len(),
+                solution.len()
+            )));
+        }
+        if solution.iter().any(|value| !(1..=9).contains(value)) {
+            return Err(AppError::Validation("solution values must be between 1 and 9".to_string()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::v1::controllers::games::check_sudoku_input;
+
+    #[test]
+    fn test_check_sudoku_input() {
+        assert!(check_sudoku_input(&[(0, 8), (80, 1)], Some(&[5; 79])).is_ok());
+        assert!(check_sudoku_input(&[(0, 8), (80, 1)], None).is_ok());
+        assert!(check_sudoku_input(&[(81, 8)], None).is_err());
+        assert!(check_sudoku_input(&[(0, 8), (0, 8)], None).is_err());
+        assert!(check_sudoku_input(&[(0, 10)], None).is_err());
+        assert!(check_sudoku_input(&[(0, 8)], Some(&[5; 81])).is_err());
+        assert!(check_sudoku_input(&[], Some(&[0; 81])).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::auth::{AuthHeaders, Authenticated};
+use crate::api::v1::controllers::games::battle::submit_solution::finish_private_submission;
+use crate::api::v1::errors::{AppError, ErrorBody};
+use crate::storage::{ProofJob, ProofJobStatus};
+use axum::extract::{Path, State};
+use axum::Json;
+use ic_agent::export::Principal;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofJobRes {
+    status: ProofJobStatus,
+    /// Hex encoded proof bytes, set once the job succeeded
+    proof: Option<String>,
+    /// Hex encoded public values, set once the job succeeded
+    public_values: Option<String>,
+    error: Option<String>,
+}
+
+impl From<ProofJob> for ProofJobRes {
+    fn from(job: ProofJob) -> Self {
+        Self {
+            status: job.status,
+            proof: job.proof,
+            public_values: job.public_values,
+            error: job.error,
+        }
+    }
+}
+
+/// The caller must have requested the proof or contributed to the submission waiting for it.
+#[utoipa::path(
+    get,
+    path = "/api/v1/games/proofs/{job_id}",
+    tag = "proofs",
+    params(("job_id" = String, Path, description = "Proof job id"), AuthHeaders),
+    responses(
+        (status = 200, body = ProofJobRes),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
+pub async fn get_proof_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+    Authenticated { caller, .. }: Authenticated<()>,
+) -> Result<Json<ProofJobRes>, AppError> {
+    let job = owned_proof_job(&state, &job_id, caller).await?;
+    Ok(Json(job.into()))
+}
+
+/// The caller must have requested the proof or contributed to the submission waiting for it.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/games/proofs/{job_id}",
+    tag = "proofs",
+    params(("job_id" = String, Path, description = "Proof job id"), AuthHeaders),
+    responses(
+        (status = 200, description = "The cancelled job", body = ProofJobRes),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
+pub async fn cancel_proof_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+    Authenticated { caller, .. }: Authenticated<()>,
+) -> Result<Json<ProofJobRes>, AppError> {
+    owned_proof_job(&state, &job_id, caller).await?;
+    let job = state.proof_queue.cancel(&job_id).await?;
+    finish_private_submission(&state, &job).await?;
+    Ok(Json(job.into()))
+}
+
+async fn owned_proof_job(state: &AppState, job_id: &str, caller: Principal) -> Result<ProofJob, AppError> {
+    let job = state
+        .storage
+        .get_proof_job(job_id)
+        .await?
+        .ok_or(AppError::NotFound(format!("Proof job {} does not exist", job_id)))?;
+    if job.owner == Some(caller) {
+        return Ok(job);
+    }
+    let contributor = state
+        .storage
+        .get_submission_by_proof_job(job_id)
+        .await?
+        .is_some_and(|submission| submission.player_contributions.iter().any(|c| c.player == caller));
+    if !contributor {
+        return Err(AppError::Forbidden(format!("{} cannot access proof job {}", caller, job_id)));
+    }
+    Ok(job)
+}
@@ -0,0 +1,26 @@
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::errors::{AppError, ErrorBody};
+use crate::api::v1::utils::ic_caller::VerifierContract;
+use axum::extract::{Path, State};
+use axum::Json;
+use verifier::types::VerificationReceipt;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/games/receipts/{id}",
+    tag = "proofs",
+    params(("id" = u64, Path, description = "Receipt id")),
+    responses(
+        (status = 200, description = "Receipt checked against the certified data of the verifier canister", body = VerificationReceipt),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
+pub async fn get_verification_receipt(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> Result<Json<VerificationReceipt>, AppError> {
+    let verifier = state.sudoku_contract.get_global_state().await?.verifier;
+    let verifier_contract = VerifierContract::new(verifier, state.sudoku_contract.agent().clone());
+    let receipt = verifier_contract.get_certified_receipt(id).await?;
+    Ok(Json(receipt))
+}
\ No newline at end of file
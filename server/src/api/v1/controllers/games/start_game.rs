@@ -1,37 +1,85 @@
-use crate::api::v1::errors::AppError;
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::auth::{AuthHeaders, Authenticated};
+use crate::api::v1::controllers::games::battle::sync_battle;
+use crate::api::v1::controllers::games::check_sudoku_input;
+use crate::api::v1::errors::{AppError, ErrorBody};
+use crate::api::v1::utils::team_state::set_team_puzzle;
+use axum::extract::State;
 use axum::Json;
 use serde::{Deserialize, Serialize};
-use crate::api::v1::utils::ic_caller::SudokuContract;
-use crate::config::env_config::env;
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct StartGameReq {
     battle_id: usize,
+    #[schema(value_type = Vec<Vec<u8>>)]
     initial_state: Vec<(u8, u8)>,
 }
 
-pub async fn start_game(Json(game): Json<StartGameReq>) -> Result<Json<()>, AppError> {
-    let env = env();
-    let agent = SudokuContract::agent_from_env(env).await?;
-    let sudoku_contract = SudokuContract::from_env(env, &agent)?;
-    sudoku_contract.start_game(game.battle_id, game.initial_state).await?;
+/// Only the creator of the battle can start it. The puzzle is then given to the teams of the
+/// battle, an error means the game started but some team did not get it.
+#[utoipa::path(
+    post,
+    path = "/api/v1/games/start-game",
+    tag = "battle",
+    params(AuthHeaders),
+    request_body = StartGameReq,
+    responses(
+        (status = 200, description = "Game started"),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
+pub async fn start_game(
+    State(state): State<AppState>,
+    Authenticated { caller, body: game }: Authenticated<StartGameReq>,
+) -> Result<Json<()>, AppError> {
+    check_sudoku_input(&game.initial_state, None)?;
+    let creator = match state.storage.get_battle(game.battle_id).await? {
+        Some(battle) => battle.creator,
+        None => sync_battle(&state, game.battle_id).await?.creator,
+    };
+    if creator != caller {
+        return Err(AppError::Forbidden(format!(
+            "{} is not the creator of battle {}",
+            caller, game.battle_id
+        )));
+    }
+    state.sudoku_contract.start_game(game.battle_id, game.initial_state.clone()).await?;
+    sync_battle(&state, game.battle_id).await?;
+
+    // The cells the members filled on the givens are emptied, so only the node can fail here
+    for team in state.storage.list_teams(game.battle_id).await? {
+        set_team_puzzle(&state.calimero, &team.context, &game.initial_state).await?;
+    }
     Ok(Json(()))
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::api::v1::app_state::AppState;
+    use crate::api::v1::auth::Authenticated;
     use crate::api::v1::controllers::games::start_game::{start_game, StartGameReq};
     use crate::config::config_app;
-    use axum::Json;
+    use crate::config::env_config::env;
+    use axum::extract::State;
+    use ic_agent::export::Principal;
+    use std::str::FromStr;
 
     #[tokio::test]
     async fn test_start_game() {
         config_app().await;
-        let res = start_game(Json(StartGameReq {
-            battle_id: 4,
-            initial_state: initial_state(),
-        }))
+        let state = AppState::from_env(env()).await.unwrap();
+        let res = start_game(State(state), Authenticated {
+            caller: Principal::from_str(
+                "pnklf-ojyec-2l2al-nuxfq-3vss6-udii2-2txam-wusja-usc54-n5uos-gae",
+            )
+            .unwrap(),
+            body: StartGameReq {
+                battle_id: 4,
+                initial_state: initial_state(),
+            },
+        })
             .await
             .unwrap();
         dbg!(res.0);
@@ -40,6 +88,7 @@ mod tests {
     fn initial_state() -> Vec<(u8, u8)> {
         vec![(0, 8), (1, 7), (7, 9), (14, 8), (17, 1)]
     }
+    #[allow(dead_code)]
     fn solution() -> Vec<u8> {
         vec![
             1, 4, 5, 6, 2, 3, 4, 5, 9, 2, 3, 6, 7, 2, 3, 6, 1, 7, 9, 4, 5, 8, 1, 2, 5, 8, 4, 3, 9,
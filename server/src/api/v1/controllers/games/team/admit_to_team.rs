@@ -0,0 +1,83 @@
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::auth::{AuthHeaders, Authenticated};
+use crate::api::v1::controllers::games::battle::submit_solution::battle_of_player;
+use crate::api::v1::errors::{AppError, ErrorBody};
+use crate::api::v1::utils::team_state::admit_member;
+use axum::extract::State;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdmitToTeamReq {
+    context_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdmitToTeamRes {
+    /// Passed to `join` in the team context along with the caller's principal, valid once
+    token: String,
+}
+
+/// Admits the caller to a team: the team context only lets a new member join with the caller's
+/// principal using the returned token, which replaces any token given to the caller before. The
+/// caller must have joined the battle the team plays.
+#[utoipa::path(
+    post,
+    path = "/api/v1/games/team/admission",
+    tag = "team",
+    params(AuthHeaders),
+    request_body = AdmitToTeamReq,
+    responses(
+        (status = 200, body = AdmitToTeamRes),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
+pub async fn admit_to_team(
+    State(state): State<AppState>,
+    Authenticated {
+        caller,
+        body: AdmitToTeamReq { context_id },
+    }: Authenticated<AdmitToTeamReq>,
+) -> Result<Json<AdmitToTeamRes>, AppError> {
+    let team = state
+        .storage
+        .get_team_by_context(&context_id)
+        .await?
+        .ok_or(AppError::NotFound(format!(
+            "Context {} does not belong to any team",
+            context_id
+        )))?;
+    if let Some(battle_id) = team.battle_id {
+        let battle = battle_of_player(&state, battle_id, caller).await?;
+        if !battle.players.contains(&caller) {
+            return Err(AppError::Forbidden(format!("{} has not joined the battle", caller)));
+        }
+    }
+
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    admit_member(&state.calimero, &team.context, &caller.to_text(), &token_hash(&token)).await?;
+
+    Ok(Json(AdmitToTeamRes { token }))
+}
+
+/// Hex encoded SHA-256 of a token, as the team application hashes it.
+fn token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::v1::controllers::games::team::admit_to_team::token_hash;
+
+    #[test]
+    fn test_token_hash() {
+        assert_eq!(
+            token_hash("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -1,35 +1,71 @@
-use crate::api::v1::errors::AppError;
-use crate::api::v1::utils::calimero_cli::run_calimero_cmd;
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::auth::{AuthHeaders, Authenticated};
+use crate::api::v1::controllers::games::battle::submit_solution::member_of_team;
+use crate::api::v1::errors::{AppError, ErrorBody};
+use axum::extract::State;
 use axum::Json;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct InviteToTeamReq {
     node_public_key: String,
     context_id: String,
-    context_identity: String,
+    /// Defaults to the identity the server created the team's context with
+    #[serde(default)]
+    context_identity: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct InviteToTeamRes {
     invitation_payload: String,
 }
 
+/// Invites a node to a team. The caller must have created the team or be one of its members.
+#[utoipa::path(
+    post,
+    path = "/api/v1/games/team/invite",
+    tag = "team",
+    params(AuthHeaders),
+    request_body = InviteToTeamReq,
+    responses(
+        (status = 200, body = InviteToTeamRes),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
 pub async fn invite_to_team(
-    Json(InviteToTeamReq {
-        node_public_key,
-        context_id,
-        context_identity,
-    }): Json<InviteToTeamReq>,
+    State(state): State<AppState>,
+    Authenticated {
+        caller,
+        body: InviteToTeamReq {
+            node_public_key,
+            context_id,
+            context_identity,
+        },
+    }: Authenticated<InviteToTeamReq>,
 ) -> Result<Json<InviteToTeamRes>, AppError> {
-    let res = run_calimero_cmd(
-        "admin",
-        &format!("context invite {context_id} {context_identity} {node_public_key}",),
-    )
-    .await?;
-    let invitation_payload = res.get("data").unwrap().as_str().unwrap().to_string();
+    if node_public_key.is_empty() {
+        return Err(AppError::Validation("nodePublicKey must not be empty".to_string()));
+    }
+    let team = state
+        .storage
+        .get_team_by_context(&context_id)
+        .await?
+        .ok_or(AppError::NotFound(format!(
+            "Context {} does not belong to any team",
+            context_id
+        )))?;
+    if team.creator != Some(caller) {
+        member_of_team(&state, &team, caller).await?;
+    }
+    let context_identity = context_identity.unwrap_or(team.context.context_identity);
+
+    let invitation_payload = state
+        .calimero
+        .invite(&context_id, &context_identity, &node_public_key)
+        .await?;
 
     Ok(Json(InviteToTeamRes { invitation_payload }))
 }
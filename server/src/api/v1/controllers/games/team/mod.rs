@@ -1,61 +1,102 @@
+pub mod admit_to_team;
 pub mod invite_to_team;
 
-use crate::api::v1::errors::AppError;
-use crate::api::v1::utils::calimero_cli::run_calimero_cmd;
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::auth::{AuthHeaders, Authenticated};
+use crate::api::v1::controllers::games::battle::submit_solution::battle_of_player;
+use crate::api::v1::errors::{AppError, ErrorBody};
+use crate::api::v1::utils::team_state::set_team_puzzle;
 use crate::config::env_config::env;
+use crate::storage::TeamContext;
+use axum::extract::State;
 use axum::Json;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateTeamReq {
     node_public_key: String,
+    /// Battle the team is created for. A team without one can neither take hints nor submit
+    #[serde(default)]
+    battle_id: Option<usize>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateTeamRes {
+    team_id: i64,
     invitation_payload: String,
     context_id: String,
     context_identity: String
 }
 
+/// Creates the Calimero context of a new team and invites the caller's node to it. The caller
+/// must have joined the battle the team is created for.
+#[utoipa::path(
+    post,
+    path = "/api/v1/games/team",
+    tag = "team",
+    params(AuthHeaders),
+    request_body = CreateTeamReq,
+    responses(
+        (status = 200, body = CreateTeamRes),
+        (status = "default", description = "Error", body = ErrorBody),
+    )
+)]
 pub async fn create_new_team(
-    Json(CreateTeamReq { node_public_key }): Json<CreateTeamReq>,
+    State(state): State<AppState>,
+    Authenticated {
+        caller,
+        body: CreateTeamReq {
+            node_public_key,
+            battle_id,
+        },
+    }: Authenticated<CreateTeamReq>,
 ) -> Result<Json<CreateTeamRes>, AppError> {
-    let res = run_calimero_cmd(
-        "admin",
-        &format!(
-            "context create --application-id {} --protocol near",
-            env().APPLICATION_ID
-        ),
-    )
-    .await?;
-    let context_id = res
-        .get("data")
-        .unwrap()
-        .get("contextId")
-        .unwrap()
-        .as_str()
-        .unwrap()
-        .to_string();
-    let context_identity = res
-        .get("data")
-        .unwrap()
-        .get("memberPublicKey")
-        .unwrap()
-        .as_str()
-        .unwrap()
-        .to_string();
+    if node_public_key.is_empty() {
+        return Err(AppError::Validation("nodePublicKey must not be empty".to_string()));
+    }
+    let battle = match battle_id {
+        Some(battle_id) => Some(battle_of_player(&state, battle_id, caller).await?),
+        None => None,
+    };
+    if let Some(battle) = &battle {
+        if !battle.players.contains(&caller) {
+            return Err(AppError::Forbidden(format!("{} has not joined the battle", caller)));
+        }
+    }
+    let context = state
+        .calimero
+        .create_context(&env().APPLICATION_ID, "near")
+        .await?;
+    let context_id = context.context_id;
+    let context_identity = context.member_public_key;
+    let invitation_payload = state
+        .calimero
+        .invite(&context_id, &context_identity, &node_public_key)
+        .await?;
 
-    let res = run_calimero_cmd(
-        "admin",
-        &format!("context invite {context_id} {context_identity} {node_public_key}",),
-    )
-    .await?;
-    let invitation_payload = res.get("data").unwrap().as_str().unwrap().to_string();
+    let team = state
+        .storage
+        .create_team(
+            battle_id,
+            caller,
+            TeamContext {
+                context_id: context_id.clone(),
+                context_identity: context_identity.clone(),
+                application_id: env().APPLICATION_ID.clone(),
+            },
+        )
+        .await?;
+
+    // Teams formed after the start of the battle get the puzzle right away
+    if let Some(initial_state) = battle.and_then(|battle| battle.initial_state) {
+        set_team_puzzle(&state.calimero, &team.context, &initial_state).await?;
+    }
 
     Ok(Json(CreateTeamRes {
+        team_id: team.id,
         invitation_payload,
         context_id,
         context_identity
@@ -65,15 +106,24 @@ pub async fn create_new_team(
 #[cfg(test)]
 mod tests {
     use crate::config::config_app;
-    use axum::Json;
+    use crate::config::env_config::env;
+    use axum::extract::State;
+    use crate::api::v1::app_state::AppState;
+    use crate::api::v1::auth::Authenticated;
     use crate::api::v1::controllers::games::team::{create_new_team, CreateTeamReq};
+    use ic_agent::export::Principal;
 
     #[tokio::test]
     async fn test_create_new_team() {
         config_app().await;
-        let res = create_new_team(Json(CreateTeamReq {
-            node_public_key: "".to_string(),
-        }))
+        let state = AppState::from_env(env()).await.unwrap();
+        let res = create_new_team(State(state), Authenticated {
+            caller: Principal::anonymous(),
+            body: CreateTeamReq {
+                node_public_key: "".to_string(),
+                battle_id: None,
+            },
+        })
         .await
         .unwrap();
         dbg!(&res.0);
@@ -0,0 +1,60 @@
+use crate::api::v1::app_state::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthRes {
+    pub healthy: bool,
+    pub replica_health_status: Option<String>,
+    pub game_contract_reachable: bool,
+    pub errors: Vec<String>,
+}
+
+/// Checks that the replica is healthy and the game canister answers queries.
+#[utoipa::path(
+    get,
+    path = "/api/v1/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "Replica and game canister are reachable", body = HealthRes),
+        (status = 503, description = "Replica or game canister is unreachable", body = HealthRes),
+    )
+)]
+pub async fn get_health(State(state): State<AppState>) -> (StatusCode, Json<HealthRes>) {
+    let mut errors = vec![];
+
+    let replica_health_status = match state.sudoku_contract.agent().status().await {
+        Ok(status) => status.replica_health_status,
+        Err(e) => {
+            errors.push(format!("replica is unreachable: {}", e));
+            None
+        }
+    };
+    let game_contract_reachable = match state.sudoku_contract.get_global_state().await {
+        Ok(_) => true,
+        Err(e) => {
+            errors.push(format!("game contract is unreachable: {}", e));
+            false
+        }
+    };
+
+    let healthy = replica_health_status.as_deref() == Some("healthy") && game_contract_reachable;
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(HealthRes {
+            healthy,
+            replica_health_status,
+            game_contract_reachable,
+            errors,
+        }),
+    )
+}
\ No newline at end of file
@@ -1 +1,2 @@
-pub mod games;
\ No newline at end of file
+pub mod games;
+pub mod health;
\ No newline at end of file
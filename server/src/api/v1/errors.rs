@@ -1,20 +1,167 @@
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
+use axum::Json;
+use ic_agent::AgentError;
+use serde::Serialize;
 use thiserror::Error;
+use utoipa::ToSchema;
 
 #[derive(Error, Debug)]
 pub enum AppError {
+    #[error("invalid request: {0}")]
+    Validation(String),
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
+    /// The caller is authenticated but not allowed to act on the resource
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("conflict: {0}")]
+    Conflict(String),
+    /// The caller or the server has too much work pending to accept more
+    #[error("too many requests: {0}")]
+    TooManyRequests(String),
+    /// A call to a canister failed or was rejected, its cause is only logged
+    #[error("canister error: {0}")]
+    Canister(String),
+    /// The admin Calimero node failed or answered something unexpected
+    #[error("calimero error: {0}")]
+    Calimero(String),
+    #[error("prover unavailable: {0}")]
+    Prover(String),
     #[error("unknown error: {0}")]
-    Unknown(#[from] anyhow::Error),
+    Unknown(anyhow::Error),
+}
+
+/// JSON body of every error response.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ErrorBody {
+    /// Stable identifier of the kind of error, e.g. `not_found`
+    #[schema(value_type = String)]
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl AppError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Canister(_) | AppError::Calimero(_) => StatusCode::BAD_GATEWAY,
+            AppError::Prover(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Validation(_) => "validation_error",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::TooManyRequests(_) => "too_many_requests",
+            AppError::Canister(_) => "canister_error",
+            AppError::Calimero(_) => "calimero_error",
+            AppError::Prover(_) => "prover_unavailable",
+            AppError::Unknown(_) => "internal_error",
+        }
+    }
+
+    fn body(&self) -> ErrorBody {
+        let message = match self {
+            AppError::Validation(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::NotFound(message)
+            | AppError::Conflict(message)
+            | AppError::TooManyRequests(message)
+            | AppError::Calimero(message)
+            | AppError::Prover(message) => message.clone(),
+            // The causes can hold agent, canister or database internals, they are only logged
+            AppError::Canister(_) => "Canister call failed".to_string(),
+            AppError::Unknown(_) => "Internal server error".to_string(),
+        };
+        ErrorBody {
+            code: self.code(),
+            message,
+        }
+    }
+}
+
+/// Helpers returning `anyhow::Result` can fail with a typed error by wrapping an [`AppError`],
+/// which is unwrapped here. Agent errors are canister call failures.
+impl From<anyhow::Error> for AppError {
+    fn from(e: anyhow::Error) -> Self {
+        let e = match e.downcast::<AppError>() {
+            Ok(e) => return e,
+            Err(e) => e,
+        };
+        match e.downcast::<AgentError>() {
+            Ok(e) => AppError::Canister(e.to_string()),
+            Err(e) => AppError::Unknown(e),
+        }
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> axum::response::Response {
-        let (status, message) = match self {
-            AppError::Unknown(e) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
-            }
-        };
-        (status, message).into_response()
+        match &self {
+            AppError::Canister(message) => eprintln!("Canister error: {}", message),
+            AppError::Unknown(e) => eprintln!("Internal error: {:?}", e),
+            _ => {}
+        }
+        (self.status(), Json(self.body())).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::v1::errors::AppError;
+    use anyhow::{anyhow, Context};
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_anyhow() {
+        let e = AppError::from(anyhow!(AppError::NotFound("battle 4".to_string())));
+        assert_eq!(e.status(), StatusCode::NOT_FOUND);
+
+        let e = AppError::from(
+            Err::<(), _>(AppError::Conflict("already joined".to_string()))
+                .context("Fail to join battle")
+                .unwrap_err(),
+        );
+        assert_eq!(e.status(), StatusCode::CONFLICT);
+
+        let e = AppError::from(anyhow!("disk is full"));
+        assert_eq!(e.status(), StatusCode::INTERNAL_SERVER_ERROR);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_error_body() {
+        let body = AppError::Validation("solution must have 81 cells".to_string()).body();
+        assert_eq!(
+            serde_json::to_value(&body).unwrap(),
+            json!({
+                "code": "validation_error",
+                "message": "solution must have 81 cells",
+            })
+        );
+
+        let body = AppError::from(anyhow!("disk is full").context("Fail to store battle")).body();
+        assert_eq!(body.code, "internal_error");
+        assert_eq!(body.message, "Internal server error");
+
+        let body = AppError::Canister("Fail to start game: connection refused".to_string()).body();
+        assert_eq!(body.code, "canister_error");
+        assert_eq!(body.message, "Canister call failed");
+
+        assert_eq!(AppError::Forbidden("not a solver".to_string()).status(), StatusCode::FORBIDDEN);
+    }
+}
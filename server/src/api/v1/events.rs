@@ -0,0 +1,211 @@
+use crate::storage::{BattleRecord, ProofJobStatus, SubmissionStatus};
+use ic_agent::export::Principal;
+use serde::Serialize;
+use sudoku::game::{PlayerContribution, SudokuGame};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum BattleEvent {
+    BattleCreated {
+        battle_id: usize,
+        creator: Principal,
+        deposit_price: u128,
+    },
+    PlayerJoined {
+        battle_id: usize,
+        player: Principal,
+    },
+    GameStarted {
+        battle_id: usize,
+        initial_state: Vec<(u8, u8)>,
+    },
+    /// The proof of a private submission progressed. The job id is only given to its owner.
+    ProofJobUpdated {
+        battle_id: usize,
+        submission_id: i64,
+        status: ProofJobStatus,
+    },
+    SubmissionUpdated {
+        battle_id: usize,
+        submission_id: i64,
+        status: SubmissionStatus,
+        error: Option<String>,
+    },
+    /// A solution was accepted and the prize pool paid out to the winners
+    BattleFinished {
+        battle_id: usize,
+        winners: Vec<PlayerContribution>,
+        verification_receipt: Option<u64>,
+    },
+}
+
+impl BattleEvent {
+    pub fn battle_id(&self) -> usize {
+        match self {
+            BattleEvent::BattleCreated { battle_id, .. }
+            | BattleEvent::PlayerJoined { battle_id, .. }
+            | BattleEvent::GameStarted { battle_id, .. }
+            | BattleEvent::ProofJobUpdated { battle_id, .. }
+            | BattleEvent::SubmissionUpdated { battle_id, .. }
+            | BattleEvent::BattleFinished { battle_id, .. } => *battle_id,
+        }
+    }
+
+    /// Name of the event, used as the SSE event type
+    pub fn name(&self) -> &'static str {
+        match self {
+            BattleEvent::BattleCreated { .. } => "battleCreated",
+            BattleEvent::PlayerJoined { .. } => "playerJoined",
+            BattleEvent::GameStarted { .. } => "gameStarted",
+            BattleEvent::ProofJobUpdated { .. } => "proofJobUpdated",
+            BattleEvent::SubmissionUpdated { .. } => "submissionUpdated",
+            BattleEvent::BattleFinished { .. } => "battleFinished",
+        }
+    }
+}
+
+/// Events describing how a battle changed since it was last mirrored.
+pub fn battle_events(old: Option<&BattleRecord>, battle_id: usize, game: &SudokuGame) -> Vec<BattleEvent> {
+    let mut events = vec![];
+    if old.is_none() {
+        events.push(BattleEvent::BattleCreated {
+            battle_id,
+            creator: game.creator,
+            deposit_price: game.deposit_price,
+        });
+    }
+    let old_players = old.map(|old| old.players.as_slice()).unwrap_or_default();
+    for player in game.players.iter().filter(|player| !old_players.contains(player)) {
+        events.push(BattleEvent::PlayerJoined {
+            battle_id,
+            player: *player,
+        });
+    }
+    if let Some(initial_state) = &game.initial_state {
+        if old.is_none_or(|old| old.initial_state.is_none()) {
+            events.push(BattleEvent::GameStarted {
+                battle_id,
+                initial_state: initial_state.clone(),
+            });
+        }
+    }
+    if let Some(winners) = &game.winners {
+        if old.is_none_or(|old| !old.finished) {
+            events.push(BattleEvent::BattleFinished {
+                battle_id,
+                winners: winners.clone(),
+                verification_receipt: game.verification_receipt,
+            });
+        }
+    }
+    events
+}
+
+/// Fans battle events out to the SSE subscribers.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<BattleEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: BattleEvent) {
+        // nobody listening is not an error
+        let _ = self.sender.send(event);
+    }
+
+    /// Events of one battle published from now on. Events missed by a slow subscriber are
+    /// skipped.
+    pub fn subscribe(&self, battle_id: usize) -> impl Stream<Item = BattleEvent> {
+        BroadcastStream::new(self.sender.subscribe())
+            .filter_map(|event| event.ok())
+            .filter(move |event| event.battle_id() == battle_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::v1::events::{battle_events, BattleEvent, EventBus};
+    use crate::storage::{BattleRecord, ProofJobStatus};
+    use ic_agent::export::Principal;
+    use std::str::FromStr;
+    use sudoku::game::{PlayerContribution, SudokuGame};
+    use tokio_stream::StreamExt;
+
+    fn player() -> Principal {
+        Principal::from_str("pnklf-ojyec-2l2al-nuxfq-3vss6-udii2-2txam-wusja-usc54-n5uos-gae").unwrap()
+    }
+
+    fn game() -> SudokuGame {
+        SudokuGame {
+            initial_state: None,
+            creator: player(),
+            deposit_price: 1,
+            service_fee: 100,
+            players: vec![],
+            solution: None,
+            winners: None,
+            claimed: false,
+            verification_receipt: None,
+            hint_budget: 3,
+            hint_penalty: 0.1,
+            hints: vec![],
+            winning_team: None,
+        }
+    }
+
+    #[test]
+    fn test_battle_events() {
+        let mut game = game();
+        let events = battle_events(None, 4, &game);
+        assert!(matches!(events[..], [BattleEvent::BattleCreated { battle_id: 4, .. }]));
+
+        let old = BattleRecord::from_game(4, &game);
+        assert!(battle_events(Some(&old), 4, &game).is_empty());
+
+        game.players.push(player());
+        game.initial_state = Some(vec![(0, 8)]);
+        let events = battle_events(Some(&old), 4, &game);
+        assert!(matches!(
+            events[..],
+            [BattleEvent::PlayerJoined { .. }, BattleEvent::GameStarted { .. }]
+        ));
+
+        let old = BattleRecord::from_game(4, &game);
+        game.solution = Some(sudoku::game::GameSolution::Public(vec![]));
+        game.winners = Some(vec![PlayerContribution {
+            player: player(),
+            percent: 1.0,
+        }]);
+        let events = battle_events(Some(&old), 4, &game);
+        assert!(matches!(events[..], [BattleEvent::BattleFinished { .. }]));
+        assert!(battle_events(Some(&BattleRecord::from_game(4, &game)), 4, &game).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_filters_by_battle() {
+        let bus = EventBus::new(16);
+        let mut events = Box::pin(bus.subscribe(2));
+        for battle_id in [1, 2] {
+            bus.publish(BattleEvent::ProofJobUpdated {
+                battle_id,
+                submission_id: battle_id as i64,
+                status: ProofJobStatus::Running,
+            });
+        }
+
+        let event = events.next().await.unwrap();
+        assert!(matches!(event, BattleEvent::ProofJobUpdated { battle_id: 2, .. }));
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({"type": "proofJobUpdated", "battleId": 2, "submissionId": 2, "status": "running"})
+        );
+    }
+}
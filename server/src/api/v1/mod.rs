@@ -2,4 +2,10 @@ pub mod controllers;
 pub mod routes;
 pub mod utils;
 pub mod errors;
-pub mod app_state;
\ No newline at end of file
+pub mod app_state;
+pub mod proof_queue;
+pub mod events;
+pub mod battle_poller;
+pub mod auth;
+pub mod extract;
+pub mod openapi;
\ No newline at end of file
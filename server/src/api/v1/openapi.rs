@@ -0,0 +1,68 @@
+use crate::api::v1::controllers::games::battle::{
+    battle_events, battle_info, hint, join_battle, submit_solution, team_contributions,
+};
+use crate::api::v1::controllers::games::team::{admit_to_team, invite_to_team};
+use crate::api::v1::controllers::games::{
+    battle, game_info, generate_proof, proofs, receipt, start_game, team,
+};
+use crate::api::v1::controllers::health;
+use utoipa::OpenApi;
+
+/// OpenAPI document of the `/api/v1` routes, served at `/api/v1/openapi.json`.
+///
+/// A copy is committed as `server/openapi.json` for the frontend client, the
+/// `test_openapi_spec_is_up_to_date` test fails when it drifts from the handlers.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Cali MOBA server API"),
+    paths(
+        health::get_health,
+        game_info::get_game_info,
+        battle::create_new_battle,
+        join_battle::join_battle,
+        battle_info::get_battle_info,
+        battle_events::get_battle_events,
+        submit_solution::submit_battle_solution,
+        submit_solution::submit_team_solution,
+        team_contributions::get_team_contributions,
+        hint::reveal_hint,
+        start_game::start_game,
+        generate_proof::generate_proof,
+        proofs::get_proof_job,
+        proofs::cancel_proof_job,
+        receipt::get_verification_receipt,
+        team::create_new_team,
+        invite_to_team::invite_to_team,
+        admit_to_team::admit_to_team,
+    ),
+    tags(
+        (name = "health"),
+        (name = "games", description = "Game configuration"),
+        (name = "battle", description = "Battles, requests changing them are signed by the player"),
+        (name = "proofs", description = "Proof jobs and verification receipts"),
+        (name = "team", description = "Calimero contexts of the teams"),
+    )
+)]
+pub struct ApiDoc;
+
+#[cfg(test)]
+mod tests {
+    use crate::api::v1::openapi::ApiDoc;
+    use std::fs;
+    use std::path::PathBuf;
+    use utoipa::OpenApi;
+
+    /// Run with `UPDATE_OPENAPI=1` to rewrite `server/openapi.json` after changing the API.
+    #[test]
+    fn test_openapi_spec_is_up_to_date() {
+        let spec = ApiDoc::openapi().to_pretty_json().unwrap() + "\n";
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("openapi.json");
+        if std::env::var("UPDATE_OPENAPI").is_ok() {
+            fs::write(&path, &spec).unwrap();
+        }
+        assert!(
+            fs::read_to_string(&path).unwrap() == spec,
+            "openapi.json is outdated, run `UPDATE_OPENAPI=1 cargo test openapi` to update it"
+        );
+    }
+}
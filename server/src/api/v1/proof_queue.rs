@@ -0,0 +1,261 @@
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::controllers::games::battle::submit_solution::finish_private_submission;
+use crate::api::v1::errors::AppError;
+use crate::api::v1::events::{BattleEvent, EventBus};
+use crate::games::sudoku::SudokuGame;
+use crate::games::Game;
+use crate::storage::{now, ProofJob, ProofJobStatus, Storage};
+use anyhow::{anyhow, bail};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex;
+use tokio::runtime::Handle;
+use tokio::task::AbortHandle;
+
+/// Runs proof jobs in the background.
+///
+/// Jobs are stored before being queued, so the ones still queued or running when the server
+/// stops are picked up again by [`ProofQueue::start`]. Each job keeps a prover busy for minutes,
+/// so the unfinished ones are bounded in total and per owner.
+pub struct ProofQueue {
+    storage: Arc<dyn Storage>,
+    events: EventBus,
+    capacity: usize,
+    jobs_per_owner: usize,
+    /// Held from counting the unfinished jobs to storing a new one, so concurrent submissions
+    /// cannot overshoot the limits
+    admission: Mutex<()>,
+    sender: UnboundedSender<String>,
+    receiver: Mutex<Option<UnboundedReceiver<String>>>,
+    /// Jobs being proven. Status changes happen while holding this lock so that a cancelled job
+    /// is never overwritten by a worker finishing at the same time.
+    running: Mutex<HashMap<String, AbortHandle>>,
+}
+
+impl ProofQueue {
+    pub fn new(storage: Arc<dyn Storage>, events: EventBus, capacity: usize, jobs_per_owner: usize) -> Self {
+        let (sender, receiver) = unbounded_channel();
+        Self {
+            storage,
+            events,
+            capacity,
+            jobs_per_owner,
+            admission: Mutex::new(()),
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Re-queues the jobs left unfinished by a previous run and spawns the workers.
+    pub async fn start(state: AppState, workers: usize) -> anyhow::Result<()> {
+        let queue = &state.proof_queue;
+        let receiver = queue
+            .receiver
+            .lock()
+            .await
+            .take()
+            .ok_or(anyhow!("Proof queue is already started"))?;
+
+        for mut job in queue.storage.list_unfinished_proof_jobs().await? {
+            if job.status == ProofJobStatus::Running {
+                job.status = ProofJobStatus::Queued;
+                job.updated_at = now();
+                queue.save(&job).await?;
+            }
+            queue.enqueue(job.id)?;
+        }
+
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..workers.max(1) {
+            tokio::spawn(run_worker(state.clone(), receiver.clone()));
+        }
+        Ok(())
+    }
+
+    /// Stores a new job and queues it.
+    pub async fn submit(&self, job: ProofJob) -> anyhow::Result<()> {
+        self.insert(&job).await?;
+        self.publish(&job).await?;
+        self.enqueue(job.id)
+    }
+
+    /// Stores a new job, unless the queue is full or its owner already has the most unfinished
+    /// jobs allowed.
+    pub async fn insert(&self, job: &ProofJob) -> anyhow::Result<()> {
+        let _admission = self.admission.lock().await;
+        let unfinished = self.storage.list_unfinished_proof_jobs().await?;
+        if unfinished.len() >= self.capacity {
+            bail!(AppError::TooManyRequests(
+                "Proof queue is full, retry once some proofs are done".to_string()
+            ));
+        }
+        if let Some(owner) = job.owner {
+            let owned = unfinished.iter().filter(|queued| queued.owner == Some(owner)).count();
+            if owned >= self.jobs_per_owner {
+                bail!(AppError::TooManyRequests(format!(
+                    "{} already has {} proof jobs queued or running",
+                    owner, owned
+                )));
+            }
+        }
+        self.storage.insert_proof_job(job).await
+    }
+
+    /// Queues a job that is already stored.
+    pub fn enqueue(&self, job_id: String) -> anyhow::Result<()> {
+        self.sender.send(job_id).map_err(|e| {
+            AppError::Prover(format!("Fail to queue proof job {}: proof queue is closed", e.0))
+        })?;
+        Ok(())
+    }
+
+    /// Cancels a queued or running job. Proving done by the local CPU prover cannot be
+    /// interrupted, its result is discarded instead.
+    pub async fn cancel(&self, job_id: &str) -> anyhow::Result<ProofJob> {
+        let mut running = self.running.lock().await;
+        let mut job = self
+            .storage
+            .get_proof_job(job_id)
+            .await?
+            .ok_or(AppError::NotFound(format!("Proof job {} does not exist", job_id)))?;
+        if job.status.is_finished() {
+            bail!(AppError::Conflict(format!(
+                "Proof job {} is already {}",
+                job_id,
+                job.status.as_str()
+            )));
+        }
+        if let Some(handle) = running.remove(job_id) {
+            handle.abort();
+        }
+        job.status = ProofJobStatus::Cancelled;
+        job.updated_at = now();
+        self.save(&job).await?;
+        Ok(job)
+    }
+
+    async fn save(&self, job: &ProofJob) -> anyhow::Result<()> {
+        self.storage.update_proof_job(job).await?;
+        self.publish(job).await
+    }
+
+    /// Tells the subscribers of the job's battle about the status of the submission's proof.
+    pub async fn publish(&self, job: &ProofJob) -> anyhow::Result<()> {
+        if job.battle_id.is_none() {
+            return Ok(());
+        }
+        if let Some(submission) = self.storage.get_submission_by_proof_job(&job.id).await? {
+            self.events.publish(BattleEvent::ProofJobUpdated {
+                battle_id: submission.battle_id,
+                submission_id: submission.id,
+                status: job.status,
+            });
+        }
+        Ok(())
+    }
+}
+
+async fn run_worker(state: AppState, receiver: Arc<Mutex<UnboundedReceiver<String>>>) {
+    loop {
+        let Some(job_id) = receiver.lock().await.recv().await else {
+            return;
+        };
+        if let Err(e) = run_job(&state, &job_id).await {
+            eprintln!("Proof job {} failed: {:?}", job_id, e);
+        }
+    }
+}
+
+async fn run_job(state: &AppState, job_id: &str) -> anyhow::Result<()> {
+    let queue = &state.proof_queue;
+
+    let (mut job, handle) = {
+        let mut running = queue.running.lock().await;
+        let Some(mut job) = queue.storage.get_proof_job(job_id).await? else {
+            return Ok(());
+        };
+        // cancelled while queued
+        if job.status != ProofJobStatus::Queued {
+            return Ok(());
+        }
+        job.status = ProofJobStatus::Running;
+        job.updated_at = now();
+        queue.save(&job).await?;
+
+        let game = SudokuGame {
+            initial_state: job.initial_state.clone(),
+            solution: job.solution.clone(),
+        };
+        // The local prover keeps its thread busy for minutes, away from the async workers
+        let handle = tokio::task::spawn_blocking(move || Handle::current().block_on(game.generate_proof()));
+        running.insert(job.id.clone(), handle.abort_handle());
+        (job, handle)
+    };
+
+    let res = handle.await;
+    {
+        let mut running = queue.running.lock().await;
+        // cancelled while running, the cancellation is already stored
+        if running.remove(&job.id).is_none() {
+            return Ok(());
+        }
+        match res {
+            Ok(Ok(proof)) => {
+                job.status = ProofJobStatus::Succeeded;
+                job.proof = Some(hex::encode(proof.bytes()));
+                job.public_values = Some(hex::encode(proof.public_values.to_vec()));
+            }
+            Ok(Err(e)) => {
+                job.status = ProofJobStatus::Failed;
+                job.error = Some(e.to_string());
+            }
+            Err(e) => {
+                job.status = ProofJobStatus::Failed;
+                job.error = Some(format!("Prover stopped: {}", e));
+            }
+        }
+        job.updated_at = now();
+        queue.save(&job).await?;
+    }
+
+    finish_private_submission(state, &job).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::v1::errors::AppError;
+    use crate::api::v1::events::EventBus;
+    use crate::api::v1::proof_queue::ProofQueue;
+    use crate::storage::sqlite::SqliteStorage;
+    use crate::storage::{ProofJob, ProofJobStatus, Storage};
+    use axum::http::StatusCode;
+    use ic_agent::export::Principal;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_queue_limits() {
+        let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::connect("sqlite::memory:").await.unwrap());
+        let queue = ProofQueue::new(storage.clone(), EventBus::new(16), 3, 2);
+        let alice = Principal::from_slice(&[1]);
+        let bob = Principal::from_slice(&[2]);
+        let job = |owner| ProofJob::new(None, owner, vec![], vec![0; 81]);
+
+        let first = job(alice);
+        queue.insert(&first).await.unwrap();
+        queue.insert(&job(alice)).await.unwrap();
+        let e = AppError::from(queue.insert(&job(alice)).await.unwrap_err());
+        assert_eq!(e.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        queue.insert(&job(bob)).await.unwrap();
+        let e = AppError::from(queue.insert(&job(bob)).await.unwrap_err());
+        assert_eq!(e.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // finished jobs free their slot
+        let mut first = first;
+        first.status = ProofJobStatus::Cancelled;
+        storage.update_proof_job(&first).await.unwrap();
+        queue.insert(&job(bob)).await.unwrap();
+    }
+}
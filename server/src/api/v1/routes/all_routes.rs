@@ -1,16 +1,20 @@
 use axum::routing::Router;
-use crate::api::v1::routes::games;
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::routes::{games, health, openapi};
 use tower_http::cors::{AllowHeaders, AllowMethods, Any, CorsLayer};
 
-pub fn get_all_routes() -> Router {
+pub fn get_all_routes(state: AppState) -> Router {
     Router::new().nest(
         "/api/v1",
         Router::new()
+            .merge(health::router())
             .merge(games::router())
-    ).layer(
+    )
+    .merge(openapi::router())
+    .layer(
         CorsLayer::new()
             .allow_methods(AllowMethods::any())
             .allow_headers(AllowHeaders::any())
             .allow_origin(Any)
-    )
+    ).with_state(state)
 }
@@ -3,18 +3,26 @@ use axum::{
     Router,
 };
 use axum::routing::get;
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::controllers::games::battle::battle_events::get_battle_events;
 use crate::api::v1::controllers::games::battle::battle_info::get_battle_info;
 use crate::api::v1::controllers::games::battle::create_new_battle;
+use crate::api::v1::controllers::games::battle::hint::reveal_hint;
 use crate::api::v1::controllers::games::battle::join_battle::join_battle;
-use crate::api::v1::controllers::games::battle::submit_solution::submit_battle_solution;
+use crate::api::v1::controllers::games::battle::team_contributions::get_team_contributions;
+use crate::api::v1::controllers::games::battle::submit_solution::{submit_battle_solution, submit_team_solution};
 
-pub fn router() -> Router {
+pub fn router() -> Router<AppState> {
     Router::new().nest(
         "/battle",
         Router::new()
             .merge(Router::new().route("/", post(create_new_battle)))
             .merge(Router::new().route("/join", post(join_battle)))
             .merge(Router::new().route("/info/{id}", get(get_battle_info)))
+            .merge(Router::new().route("/events/{id}", get(get_battle_events)))
             .merge(Router::new().route("/solution", post(submit_battle_solution)))
+            .merge(Router::new().route("/team-solution", post(submit_team_solution)))
+            .merge(Router::new().route("/contributions/{id}/{context_id}", get(get_team_contributions)))
+            .merge(Router::new().route("/hint", post(reveal_hint)))
     )
 }
\ No newline at end of file
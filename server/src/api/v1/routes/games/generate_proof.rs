@@ -1,6 +1,7 @@
+use crate::api::v1::app_state::AppState;
 use crate::api::v1::controllers::games::generate_proof::generate_proof;
 use axum::{routing::post, Router};
 
-pub fn router() -> Router {
+pub fn router() -> Router<AppState> {
     Router::new().route("/generate-proof", post(generate_proof))
 }
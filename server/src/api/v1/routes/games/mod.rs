@@ -1,13 +1,16 @@
 use axum::Router;
 use axum::routing::get;
+use crate::api::v1::app_state::AppState;
 use crate::api::v1::controllers::games::game_info::get_game_info;
 
 pub mod generate_proof;
 pub mod start_game;
 pub mod team;
 pub mod battle;
+pub mod receipt;
+pub mod proofs;
 
-pub fn router() -> Router {
+pub fn router() -> Router<AppState> {
     Router::new().nest(
         "/games",
         Router::new()
@@ -15,6 +18,8 @@ pub fn router() -> Router {
             .merge(battle::router())
             .merge(generate_proof::router())
             .merge(start_game::router())
-            .merge(team::router()),
+            .merge(team::router())
+            .merge(receipt::router())
+            .merge(proofs::router()),
     )
 }
@@ -0,0 +1,8 @@
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::controllers::games::proofs::{cancel_proof_job, get_proof_job};
+use axum::routing::get;
+use axum::Router;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/proofs/{job_id}", get(get_proof_job).delete(cancel_proof_job))
+}
\ No newline at end of file
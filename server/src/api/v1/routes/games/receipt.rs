@@ -0,0 +1,8 @@
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::controllers::games::receipt::get_verification_receipt;
+use axum::routing::get;
+use axum::Router;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/receipts/{id}", get(get_verification_receipt))
+}
\ No newline at end of file
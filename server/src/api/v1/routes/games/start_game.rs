@@ -1,7 +1,8 @@
+use crate::api::v1::app_state::AppState;
 use crate::api::v1::controllers::games::start_game::start_game;
 use axum::routing::post;
 use axum::Router;
 
-pub fn router() -> Router {
+pub fn router() -> Router<AppState> {
     Router::new().route("/start-game", post(start_game))
 }
\ No newline at end of file
@@ -2,14 +2,17 @@ use axum::{
     routing::post,
     Router,
 };
+use crate::api::v1::app_state::AppState;
 use crate::api::v1::controllers::games::team::create_new_team;
+use crate::api::v1::controllers::games::team::admit_to_team::admit_to_team;
 use crate::api::v1::controllers::games::team::invite_to_team::invite_to_team;
 
-pub fn router() -> Router {
+pub fn router() -> Router<AppState> {
     Router::new().nest(
         "/team",
         Router::new()
             .merge(Router::new().route("/", post(create_new_team)))
             .merge(Router::new().route("/invite", post(invite_to_team)))
+            .merge(Router::new().route("/admission", post(admit_to_team)))
     )
 }
\ No newline at end of file
@@ -0,0 +1,8 @@
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::controllers::health::get_health;
+use axum::routing::get;
+use axum::Router;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/health", get(get_health))
+}
\ No newline at end of file
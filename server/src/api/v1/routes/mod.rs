@@ -1,2 +1,4 @@
 pub mod all_routes;
-pub mod games;
\ No newline at end of file
+pub mod games;
+pub mod health;
+pub mod openapi;
\ No newline at end of file
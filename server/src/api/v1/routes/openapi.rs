@@ -0,0 +1,13 @@
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::openapi::ApiDoc;
+use axum::Router;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+/// Serves the OpenAPI document and a Swagger UI at `/api/v1/docs`. The paths are absolute
+/// because the UI fetches the document from the browser.
+pub fn router() -> Router<AppState> {
+    SwaggerUi::new("/api/v1/docs")
+        .url("/api/v1/openapi.json", ApiDoc::openapi())
+        .into()
+}
@@ -0,0 +1,277 @@
+use crate::api::v1::errors::AppError;
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CalimeroError {
+    #[error("request to the calimero node failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("calimero node answered {status}: {body}")]
+    Status { status: StatusCode, body: String },
+    #[error("calimero node answered without data")]
+    MissingData,
+    #[error("calimero method {method} failed: {error}")]
+    Execution { method: String, error: Value },
+}
+
+impl From<CalimeroError> for AppError {
+    fn from(e: CalimeroError) -> Self {
+        AppError::Calimero(e.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedContext {
+    pub context_id: String,
+    /// Identity of the node in the new context
+    pub member_public_key: String,
+}
+
+/// Every admin API response wraps its payload in `data`.
+#[derive(Deserialize)]
+struct DataRes<T> {
+    data: Option<T>,
+}
+
+#[derive(Deserialize)]
+struct IdentitiesRes {
+    identities: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RpcRes {
+    result: Option<RpcResult>,
+    error: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct RpcResult {
+    output: Option<Value>,
+}
+
+/// Client of the admin HTTP API of a Calimero node, which runs the team contexts on behalf of
+/// the server.
+#[derive(Debug, Clone)]
+pub struct CalimeroClient {
+    http: reqwest::Client,
+    node_url: String,
+}
+
+impl CalimeroClient {
+    pub fn new(node_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            node_url: node_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Creates a context running `application_id`, the node becomes its first member.
+    pub async fn create_context(
+        &self,
+        application_id: &str,
+        protocol: &str,
+    ) -> Result<CreatedContext, CalimeroError> {
+        let req = self
+            .request(Method::POST, "/admin-api/dev/contexts")
+            .json(&json!({
+                "applicationId": application_id,
+                "protocol": protocol,
+                "initializationParams": [],
+            }));
+        self.data(req).await
+    }
+
+    /// Invites the node identified by `invitee_id`, returns the payload it joins the context with.
+    pub async fn invite(
+        &self,
+        context_id: &str,
+        inviter_id: &str,
+        invitee_id: &str,
+    ) -> Result<String, CalimeroError> {
+        let req = self
+            .request(Method::POST, "/admin-api/dev/contexts/invite")
+            .json(&json!({
+                "contextId": context_id,
+                "inviterId": inviter_id,
+                "inviteeId": invitee_id,
+            }));
+        self.data(req).await
+    }
+
+    /// Identities of the members of a context.
+    pub async fn list_members(&self, context_id: &str) -> Result<Vec<String>, CalimeroError> {
+        let req = self.request(
+            Method::GET,
+            &format!("/admin-api/dev/contexts/{}/identities", context_id),
+        );
+        let res: IdentitiesRes = self.data(req).await?;
+        Ok(res.identities)
+    }
+
+    /// Removes `member_id` from a context.
+    pub async fn revoke(
+        &self,
+        context_id: &str,
+        revoker_id: &str,
+        member_id: &str,
+    ) -> Result<(), CalimeroError> {
+        let req = self
+            .request(Method::POST, "/admin-api/dev/contexts/revoke")
+            .json(&json!({
+                "contextId": context_id,
+                "revokerId": revoker_id,
+                "memberId": member_id,
+            }));
+        self.send(req).await?;
+        Ok(())
+    }
+
+    /// Calls a method of the application running in a context as `executor`. Methods without
+    /// output deserialize from `null`.
+    pub async fn call<A: Serialize, R: DeserializeOwned>(
+        &self,
+        context_id: &str,
+        executor: &str,
+        method: &str,
+        args: &A,
+    ) -> Result<R, CalimeroError> {
+        let req = self.request(Method::POST, "/jsonrpc/dev").json(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "execute",
+            "params": {
+                "contextId": context_id,
+                "method": method,
+                "argsJson": args,
+                "executorPublicKey": executor,
+            },
+        }));
+        let res: RpcRes = self.send(req).await?.json().await?;
+        if let Some(error) = res.error {
+            return Err(CalimeroError::Execution {
+                method: method.to_string(),
+                error,
+            });
+        }
+        let output = res.result.and_then(|result| result.output).unwrap_or(Value::Null);
+        serde_json::from_value(output).map_err(|e| CalimeroError::Execution {
+            method: method.to_string(),
+            error: Value::String(format!("unexpected output: {}", e)),
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http.request(method, format!("{}{}", self.node_url, path))
+    }
+
+    async fn send(&self, req: RequestBuilder) -> Result<reqwest::Response, CalimeroError> {
+        let res = req.send().await?;
+        let status = res.status();
+        if !status.is_success() {
+            let body = res.text().await.unwrap_or_default();
+            return Err(CalimeroError::Status { status, body });
+        }
+        Ok(res)
+    }
+
+    async fn data<T: DeserializeOwned>(&self, req: RequestBuilder) -> Result<T, CalimeroError> {
+        let res: DataRes<T> = self.send(req).await?.json().await?;
+        res.data.ok_or(CalimeroError::MissingData)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::api::v1::utils::calimero_client::{CalimeroClient, CalimeroError, CreatedContext};
+    use axum::extract::Path;
+    use axum::http::StatusCode;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+
+    /// Serves the subset of the admin API used by the server, with a context `context` whose
+    /// application stores a single counter.
+    pub(crate) async fn stub_node() -> String {
+        let app = Router::new()
+            .route(
+                "/admin-api/dev/contexts",
+                post(|Json(body): Json<Value>| async move {
+                    assert_eq!(body["protocol"], "near");
+                    Json(json!({"data": {"contextId": "context", "memberPublicKey": "admin"}}))
+                }),
+            )
+            .route(
+                "/admin-api/dev/contexts/invite",
+                post(|Json(body): Json<Value>| async move {
+                    Json(json!({"data": format!("invitation-for-{}", body["inviteeId"].as_str().unwrap())}))
+                }),
+            )
+            .route(
+                "/admin-api/dev/contexts/{id}/identities",
+                get(|Path(id): Path<String>| async move {
+                    if id != "context" {
+                        return Err((StatusCode::NOT_FOUND, "context not found"));
+                    }
+                    Ok(Json(json!({"data": {"identities": ["admin", "player"]}})))
+                }),
+            )
+            .route("/admin-api/dev/contexts/revoke", post(|| async { Json(json!({"data": null})) }))
+            .route(
+                "/jsonrpc/dev",
+                post(|Json(body): Json<Value>| async move {
+                    let params = &body["params"];
+                    Json(match params["method"].as_str().unwrap() {
+                        "get_counter" => json!({"jsonrpc": "2.0", "id": 1, "result": {"output": 7}}),
+                        "increment" => json!({"jsonrpc": "2.0", "id": 1, "result": {"output": null}}),
+                        method => json!({"jsonrpc": "2.0", "id": 1, "error": {"type": "FunctionCallError", "data": method}}),
+                    })
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_admin_api() {
+        let client = CalimeroClient::new(&stub_node().await);
+
+        let context = client.create_context("app", "near").await.unwrap();
+        assert_eq!(
+            context,
+            CreatedContext {
+                context_id: "context".to_string(),
+                member_public_key: "admin".to_string(),
+            }
+        );
+        assert_eq!(
+            client.invite("context", "admin", "player").await.unwrap(),
+            "invitation-for-player"
+        );
+        assert_eq!(client.list_members("context").await.unwrap(), vec!["admin", "player"]);
+        client.revoke("context", "admin", "player").await.unwrap();
+
+        match client.list_members("other").await {
+            Err(CalimeroError::Status { status, .. }) => assert_eq!(status, StatusCode::NOT_FOUND),
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call() {
+        let client = CalimeroClient::new(&stub_node().await);
+
+        let counter: u32 = client.call("context", "admin", "get_counter", &json!({})).await.unwrap();
+        assert_eq!(counter, 7);
+        let () = client.call("context", "admin", "increment", &json!({})).await.unwrap();
+        assert!(matches!(
+            client.call::<_, ()>("context", "admin", "unknown", &json!({})).await,
+            Err(CalimeroError::Execution { .. })
+        ));
+    }
+}
@@ -0,0 +1,329 @@
+use anyhow::{anyhow, bail};
+use ed25519_dalek::Verifier;
+use ic_agent::export::Principal;
+use ic_agent::hash_tree::{HashTree, LookupResult};
+use ic_agent::{Agent, Certificate};
+use p256::pkcs8::DecodePublicKey;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use spki::ObjectIdentifier;
+
+/// Domain separator of the messages signed for API requests
+pub const REQUEST_DOMAIN: &[u8] = b"\x15cali-moba-api-request";
+/// Domain separator of delegations, as defined by the IC interface specification
+const DELEGATION_DOMAIN: &[u8] = b"\x1Aic-request-auth-delegation";
+const MAX_DELEGATIONS: usize = 20;
+
+const ED25519_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+const EC_PUBLIC_KEY_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const CANISTER_SIG_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.56387.1.2");
+
+/// Delegation chain in the JSON format of `DelegationChain.toJSON()` from agent-js.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DelegationChain {
+    pub delegations: Vec<SignedDelegation>,
+    /// Hex encoded DER public key of the identity that issued the first delegation
+    pub public_key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignedDelegation {
+    pub delegation: Delegation,
+    /// Hex encoded
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Delegation {
+    /// Hex encoded DER public key the delegation is given to
+    pub pubkey: String,
+    /// Expiration in nanoseconds since the epoch, hex encoded
+    pub expiration: String,
+    /// Hex encoded canister ids the delegation is restricted to
+    pub targets: Option<Vec<String>>,
+}
+
+/// Message signed by the caller for an API request, `path` including the query string.
+pub fn request_message(method: &str, path: &str, timestamp_ms: u64, body: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(timestamp_ms.to_string().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(Sha256::digest(body));
+    [REQUEST_DOMAIN, hasher.finalize().as_slice()].concat()
+}
+
+/// Message signed by the issuer of a delegation: the representation independent hash of the
+/// delegation map, prefixed with its domain separator.
+pub fn delegation_message(pubkey: &[u8], expiration: u64, targets: Option<&[Vec<u8>]>) -> Vec<u8> {
+    let mut fields = vec![
+        hash_field("pubkey", &Sha256::digest(pubkey)),
+        hash_field("expiration", &Sha256::digest(leb128(expiration))),
+    ];
+    if let Some(targets) = targets {
+        let mut hasher = Sha256::new();
+        for target in targets {
+            hasher.update(Sha256::digest(target));
+        }
+        fields.push(hash_field("targets", &hasher.finalize()));
+    }
+    fields.sort();
+    let mut hasher = Sha256::new();
+    for field in fields {
+        hasher.update(field);
+    }
+    [DELEGATION_DOMAIN, hasher.finalize().as_slice()].concat()
+}
+
+fn hash_field(key: &str, value_hash: &[u8]) -> Vec<u8> {
+    [Sha256::digest(key.as_bytes()).as_slice(), value_hash].concat()
+}
+
+fn leb128(mut value: u64) -> Vec<u8> {
+    let mut bytes = vec![];
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+enum PublicKey {
+    Ed25519(ed25519_dalek::VerifyingKey),
+    EcdsaP256(p256::ecdsa::VerifyingKey),
+    /// Signature certified by a canister, used by Internet Identity
+    Canister { canister_id: Principal, seed: Vec<u8> },
+}
+
+impl PublicKey {
+    fn from_der(der: &[u8]) -> anyhow::Result<Self> {
+        let info = spki::SubjectPublicKeyInfoRef::try_from(der)
+            .map_err(|e| anyhow!("Invalid public key: {}", e))?;
+        let key = info.subject_public_key.raw_bytes();
+        match info.algorithm.oid {
+            ED25519_OID => Ok(PublicKey::Ed25519(ed25519_dalek::VerifyingKey::from_bytes(
+                key.try_into()
+                    .map_err(|_| anyhow!("Invalid Ed25519 public key"))?,
+            )?)),
+            EC_PUBLIC_KEY_OID => Ok(PublicKey::EcdsaP256(
+                p256::ecdsa::VerifyingKey::from_public_key_der(der)
+                    .map_err(|e| anyhow!("Unsupported ECDSA public key: {}", e))?,
+            )),
+            CANISTER_SIG_OID => {
+                let (&len, rest) = key.split_first().ok_or(anyhow!("Invalid canister signature public key"))?;
+                if rest.len() < len as usize {
+                    bail!("Invalid canister signature public key");
+                }
+                let (canister_id, seed) = rest.split_at(len as usize);
+                Ok(PublicKey::Canister {
+                    canister_id: Principal::try_from_slice(canister_id)?,
+                    seed: seed.to_vec(),
+                })
+            }
+            oid => bail!("Unsupported public key algorithm {}", oid),
+        }
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8], agent: &Agent) -> anyhow::Result<()> {
+        match self {
+            PublicKey::Ed25519(key) => {
+                let signature = ed25519_dalek::Signature::from_slice(signature)?;
+                key.verify(message, &signature)
+                    .map_err(|_| anyhow!("Invalid Ed25519 signature"))
+            }
+            PublicKey::EcdsaP256(key) => {
+                let signature = p256::ecdsa::Signature::from_slice(signature)
+                    .map_err(|_| anyhow!("Invalid ECDSA signature"))?;
+                key.verify(message, &signature)
+                    .map_err(|_| anyhow!("Invalid ECDSA signature"))
+            }
+            PublicKey::Canister { canister_id, seed } => {
+                verify_canister_signature(*canister_id, seed, message, signature, agent)
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CanisterSignature {
+    certificate: serde_bytes::ByteBuf,
+    tree: HashTree<Vec<u8>>,
+}
+
+/// Checks that the canister certified `message` for `seed`, see the canister signatures section
+/// of the IC interface specification.
+fn verify_canister_signature(
+    canister_id: Principal,
+    seed: &[u8],
+    message: &[u8],
+    signature: &[u8],
+    agent: &Agent,
+) -> anyhow::Result<()> {
+    let signature: CanisterSignature = serde_cbor::from_slice(signature)?;
+    let certificate: Certificate = serde_cbor::from_slice(&signature.certificate)?;
+    agent.verify(&certificate, canister_id)?;
+
+    let certified_data = match certificate.tree.lookup_path([
+        "canister".as_bytes(),
+        canister_id.as_slice(),
+        "certified_data".as_bytes(),
+    ]) {
+        LookupResult::Found(data) => data.to_vec(),
+        _ => bail!("Certificate doesn't contain the certified data of {}", canister_id),
+    };
+    if signature.tree.digest().as_slice() != certified_data.as_slice() {
+        bail!("Canister signature tree doesn't match the certified data");
+    }
+
+    match signature.tree.lookup_path([
+        "sig".as_bytes(),
+        Sha256::digest(seed).as_slice(),
+        Sha256::digest(message).as_slice(),
+    ]) {
+        LookupResult::Found(_) => Ok(()),
+        _ => bail!("Message is not signed by canister {}", canister_id),
+    }
+}
+
+/// Verifies the signature of a request and returns the principal of its caller.
+///
+/// Without a delegation chain `public_key` signs the request itself. With a chain, the caller is
+/// the identity that issued the first delegation and the request is signed by the key of the
+/// last one. `now_ns` is used to check that no delegation expired.
+pub fn verify_request(
+    message: &[u8],
+    signature: &[u8],
+    public_key: Option<&[u8]>,
+    chain: Option<&DelegationChain>,
+    target: Principal,
+    now_ns: u64,
+    agent: &Agent,
+) -> anyhow::Result<Principal> {
+    let (root_key, delegations) = match (chain, public_key) {
+        (Some(chain), _) => (hex::decode(&chain.public_key)?, chain.delegations.as_slice()),
+        (None, Some(public_key)) => (public_key.to_vec(), [].as_slice()),
+        (None, None) => bail!("Missing public key or delegation chain"),
+    };
+    if delegations.len() > MAX_DELEGATIONS {
+        bail!("Delegation chain is longer than {}", MAX_DELEGATIONS);
+    }
+
+    let mut signing_key = root_key.clone();
+    for SignedDelegation { delegation, signature } in delegations {
+        let pubkey = hex::decode(&delegation.pubkey)?;
+        let expiration = u64::from_str_radix(&delegation.expiration, 16)
+            .map_err(|_| anyhow!("Invalid delegation expiration {}", delegation.expiration))?;
+        if expiration < now_ns {
+            bail!("Delegation expired");
+        }
+        let targets = delegation
+            .targets
+            .as_ref()
+            .map(|targets| targets.iter().map(hex::decode).collect::<Result<Vec<_>, _>>())
+            .transpose()?;
+        if let Some(targets) = &targets {
+            if !targets.iter().any(|t| t.as_slice() == target.as_slice()) {
+                bail!("Delegation is restricted to other canisters");
+            }
+        }
+
+        let message = delegation_message(&pubkey, expiration, targets.as_deref());
+        PublicKey::from_der(&signing_key)?
+            .verify(&message, &hex::decode(signature)?, agent)?;
+        signing_key = pubkey;
+    }
+
+    PublicKey::from_der(&signing_key)?
+        .verify(message, signature, agent)?;
+    Ok(Principal::self_authenticating(&root_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::v1::utils::ic_auth::{
+        delegation_message, request_message, verify_request, DelegationChain, Delegation,
+        SignedDelegation,
+    };
+    use ed25519_dalek::pkcs8::EncodePublicKey;
+    use ed25519_dalek::{Signer, SigningKey};
+    use ic_agent::export::Principal;
+    use ic_agent::Agent;
+
+    const NOW_NS: u64 = 1_700_000_000_000_000_000;
+
+    fn key(seed: u8) -> (SigningKey, Vec<u8>) {
+        let key = SigningKey::from_bytes(&[seed; 32]);
+        let der = key.verifying_key().to_public_key_der().unwrap().into_vec();
+        (key, der)
+    }
+
+    fn agent() -> Agent {
+        Agent::builder().with_url("http://127.0.0.1:4943").build().unwrap()
+    }
+
+    fn target() -> Principal {
+        Principal::from_text("a4tbr-q4aaa-aaaaa-qaafq-cai").unwrap()
+    }
+
+    fn chain(root: &SigningKey, root_der: &[u8], session_der: &[u8], expiration: u64) -> DelegationChain {
+        let message = delegation_message(session_der, expiration, None);
+        DelegationChain {
+            delegations: vec![SignedDelegation {
+                delegation: Delegation {
+                    pubkey: hex::encode(session_der),
+                    expiration: format!("{:x}", expiration),
+                    targets: None,
+                },
+                signature: hex::encode(root.sign(&message).to_bytes()),
+            }],
+            public_key: hex::encode(root_der),
+        }
+    }
+
+    #[test]
+    fn test_verify_request_signed_by_identity() {
+        let (key, der) = key(1);
+        let message = request_message("POST", "/api/v1/games/battle/join", 1, b"{}");
+        let signature = key.sign(&message).to_bytes();
+
+        let caller = verify_request(&message, &signature, Some(&der), None, target(), NOW_NS, &agent())
+            .unwrap();
+        assert_eq!(caller, Principal::self_authenticating(&der));
+
+        let tampered = request_message("POST", "/api/v1/games/battle/join", 1, b"{\"a\":1}");
+        assert!(verify_request(&tampered, &signature, Some(&der), None, target(), NOW_NS, &agent())
+            .is_err());
+    }
+
+    #[test]
+    fn test_verify_request_with_delegation() {
+        let (root, root_der) = key(1);
+        let (session, session_der) = key(2);
+        let message = request_message("POST", "/api/v1/games/battle/join", 1, b"{}");
+        let signature = session.sign(&message).to_bytes();
+
+        let valid = chain(&root, &root_der, &session_der, NOW_NS + 1);
+        let caller = verify_request(&message, &signature, None, Some(&valid), target(), NOW_NS, &agent())
+            .unwrap();
+        // the caller is the identity behind the session key
+        assert_eq!(caller, Principal::self_authenticating(&root_der));
+
+        let expired = chain(&root, &root_der, &session_der, NOW_NS - 1);
+        assert!(verify_request(&message, &signature, None, Some(&expired), target(), NOW_NS, &agent())
+            .is_err());
+
+        // a delegation that was not issued by the root key
+        let (other, _) = key(3);
+        let forged = chain(&other, &root_der, &session_der, NOW_NS + 1);
+        assert!(verify_request(&message, &signature, None, Some(&forged), target(), NOW_NS, &agent())
+            .is_err());
+    }
+}
@@ -1,55 +1,94 @@
+use crate::api::v1::errors::AppError;
 use crate::config::env_config::Env;
-use anyhow::anyhow;
 use ic_agent::agent::AgentBuilder;
 use ic_agent::export::Principal;
 use ic_agent::identity::Secp256k1Identity;
-use ic_agent::Agent;
+use ic_agent::hash_tree::LookupResult;
+use ic_agent::{Agent, Certificate};
 use ic_utils::call::SyncCall;
 use ic_utils::canister::CanisterBuilder;
 use ic_utils::Canister;
 use sudoku::game::{GameSolution, PlayerContribution, SudokuGame};
+use sudoku::state::GlobalState;
+use verifier::types::{CertifiedReceipt, VerificationReceipt};
+use verifier::utils::{receipt_hash, receipt_key, RECEIPTS_LABEL};
 
 #[derive(Debug, Clone)]
-pub struct IcCaller<'a> {
-    pub(crate) canister: Canister<'a>,
+pub struct IcCaller {
+    pub(crate) agent: Agent,
+    pub(crate) canister_id: Principal,
 }
 
-impl<'a> IcCaller<'a> {
-    pub async fn gen_agent(pem_path: &str, replica_url: &str) -> anyhow::Result<Agent> {
+impl IcCaller {
+    /// Creates an agent for the replica at `replica_url`. The root key is only fetched from the
+    /// replica on a local network, on mainnet the hard-coded IC root key must be used.
+    pub async fn gen_agent(pem_path: &str, replica_url: &str, fetch_root_key: bool) -> anyhow::Result<Agent> {
         let identity = Secp256k1Identity::from_pem_file(pem_path)?;
         let agent = AgentBuilder::default()
             .with_identity(identity)
             .with_url(replica_url)
             .build()?;
-        agent.fetch_root_key().await?;
+        if fetch_root_key {
+            agent.fetch_root_key().await?;
+        }
         Ok(agent)
     }
 
-    pub fn new(canister_id: &str, agent: &'a Agent) -> anyhow::Result<Self> {
+    pub fn new(canister_id: &str, agent: Agent) -> anyhow::Result<Self> {
+        let canister_id = Principal::from_text(canister_id)?;
+        Ok(Self { agent, canister_id })
+    }
+
+    pub(crate) fn canister(&self) -> anyhow::Result<Canister<'_>> {
         let canister = CanisterBuilder::new()
-            .with_canister_id(canister_id)
-            .with_agent(agent)
+            .with_canister_id(self.canister_id)
+            .with_agent(&self.agent)
             .build()?;
-        Ok(Self { canister })
+        Ok(canister)
+    }
+}
+
+/// Maps a rejection of the game canister to the error returned to API clients.
+fn game_error(action: &str, e: sudoku::error::ContractError) -> AppError {
+    use sudoku::error::ContractError::*;
+    let message = format!("{}: {}", action, e);
+    match e {
+        InvalidAction(reason) if reason.ends_with("not found") => AppError::NotFound(message),
+        InvalidAction(_) => AppError::Conflict(message),
+        InvalidSolution(_) | InvalidProof(_) => AppError::Validation(message),
     }
 }
 
-pub struct SudokuContract<'a>(IcCaller<'a>);
+#[derive(Debug, Clone)]
+pub struct SudokuContract(IcCaller);
 
-impl<'a> SudokuContract<'a> {
+impl SudokuContract {
     pub async fn agent_from_env(env: &Env) -> anyhow::Result<Agent> {
-        let agent = IcCaller::gen_agent(&env.GAME_OWNER_PEM_FILE, &env.RPC_URL).await?;
+        let agent = IcCaller::gen_agent(
+            &env.GAME_OWNER_PEM_FILE,
+            &env.RPC_URL,
+            env.IC_NETWORK == "local",
+        )
+        .await?;
         Ok(agent)
     }
-    pub fn from_env(env: &Env, agent: &'a Agent) -> anyhow::Result<Self> {
+
+    pub fn from_env(env: &Env, agent: Agent) -> anyhow::Result<Self> {
         let ic_caller = IcCaller::new(&env.GAME_CONTRACT, agent)?;
         Ok(Self(ic_caller))
     }
 
+    pub fn agent(&self) -> &Agent {
+        &self.0.agent
+    }
+
+    pub fn canister_id(&self) -> Principal {
+        self.0.canister_id
+    }
+
     pub async fn get_battle_info(&self, battle_id: usize) -> anyhow::Result<SudokuGame> {
-        let request = self
-            .0
-            .canister
+        let canister = self.0.canister()?;
+        let request = canister
             .query("get_battle_info")
             .with_arg(battle_id)
             .build::<(Result<SudokuGame, sudoku::error::ContractError>,)>();
@@ -57,7 +96,16 @@ impl<'a> SudokuContract<'a> {
             .call()
             .await?
             .0
-            .map_err(|e| anyhow!("Fail to get battle info: {:?}", e))?;
+            .map_err(|e| game_error("Fail to get battle info", e))?;
+        Ok(res)
+    }
+
+    pub async fn get_global_state(&self) -> anyhow::Result<GlobalState> {
+        let canister = self.0.canister()?;
+        let request = canister
+            .query("get_global_state")
+            .build::<(GlobalState,)>();
+        let res = request.call().await?.0;
         Ok(res)
     }
 
@@ -66,25 +114,25 @@ impl<'a> SudokuContract<'a> {
         deposit_price: u128,
         service_fee: u128,
         creator: Principal,
+        hint_budget: u32,
+        hint_penalty: f32,
     ) -> anyhow::Result<usize> {
-        let request = self
-            .0
-            .canister
+        let canister = self.0.canister()?;
+        let request = canister
             .update("create_new_battle")
-            .with_args((deposit_price, service_fee, creator))
+            .with_args((deposit_price, service_fee, creator, hint_budget, hint_penalty))
             .build::<(Result<usize, sudoku::error::ContractError>,)>();
         let battle_id = request
             .call_and_wait()
             .await?
             .0
-            .map_err(|e| anyhow!("Fail to create new battle: {:?}", e))?;
+            .map_err(|e| game_error("Fail to create new battle", e))?;
         Ok(battle_id)
     }
 
     pub async fn join_battle(&self, battle_id: usize, player: Principal) -> anyhow::Result<()> {
-        let request = self
-            .0
-            .canister
+        let canister = self.0.canister()?;
+        let request = canister
             .update("join_battle")
             .with_args((battle_id, player))
             .build::<(Result<(), sudoku::error::ContractError>,)>();
@@ -92,7 +140,7 @@ impl<'a> SudokuContract<'a> {
             .call_and_wait()
             .await?
             .0
-            .map_err(|e| anyhow!("Fail to join battle : {:?}", e))?;
+            .map_err(|e| game_error("Fail to join battle", e))?;
         Ok(())
     }
 
@@ -101,9 +149,8 @@ impl<'a> SudokuContract<'a> {
         battle_id: usize,
         initial_state: Vec<(u8, u8)>,
     ) -> anyhow::Result<()> {
-        let request = self
-            .0
-            .canister
+        let canister = self.0.canister()?;
+        let request = canister
             .update("start_game")
             .with_args((battle_id, initial_state))
             .build::<(Result<(), sudoku::error::ContractError>,)>();
@@ -111,22 +158,120 @@ impl<'a> SudokuContract<'a> {
             .call_and_wait()
             .await?
             .0
-            .map_err(|e| anyhow!("Fail to start game: {:?}", e))?;
+            .map_err(|e| game_error("Fail to start game", e))?;
         Ok(())
     }
 
-    pub async fn submit_solution(&self, battle_id: usize, solution: GameSolution, player_contributions: Vec<PlayerContribution>) -> anyhow::Result<()> {
-        let request = self
+    /// Records a hint given to `team` with its current `players`, returns the hints it has left.
+    pub async fn use_hint(&self, battle_id: usize, team: String, players: Vec<Principal>) -> anyhow::Result<u32> {
+        let canister = self.0.canister()?;
+        let request = canister
+            .update("use_hint")
+            .with_args((battle_id, team, players))
+            .build::<(Result<u32, sudoku::error::ContractError>,)>();
+        let hints_left = request
+            .call_and_wait()
+            .await?
             .0
-            .canister
+            .map_err(|e| game_error("Fail to use hint", e))?;
+        Ok(hints_left)
+    }
+
+    /// Gives back a hint `team` never got with the `players` it was charged to, returns the
+    /// hints it has left.
+    pub async fn refund_hint(&self, battle_id: usize, team: String, players: Vec<Principal>) -> anyhow::Result<u32> {
+        let canister = self.0.canister()?;
+        let request = canister
+            .update("refund_hint")
+            .with_args((battle_id, team, players))
+            .build::<(Result<u32, sudoku::error::ContractError>,)>();
+        let hints_left = request
+            .call_and_wait()
+            .await?
+            .0
+            .map_err(|e| game_error("Fail to refund hint", e))?;
+        Ok(hints_left)
+    }
+
+    /// Submits a solution, on behalf of `team` if it was found by a team.
+    pub async fn submit_solution(
+        &self,
+        battle_id: usize,
+        solution: GameSolution,
+        player_contributions: Vec<PlayerContribution>,
+        team: Option<String>,
+    ) -> anyhow::Result<()> {
+        let canister = self.0.canister()?;
+        let request = canister
             .update("submit_solution")
-            .with_args((battle_id, solution, player_contributions))
+            .with_args((battle_id, solution, player_contributions, team))
             .build::<(Result<(), sudoku::error::ContractError>,)>();
         request
             .call_and_wait()
             .await?
             .0
-            .map_err(|e| anyhow!("Fail to start game: {:?}", e))?;
+            .map_err(|e| game_error("Fail to submit solution", e))?;
         Ok(())
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct VerifierContract(IcCaller);
+
+impl VerifierContract {
+    pub fn new(canister_id: Principal, agent: Agent) -> Self {
+        Self(IcCaller { agent, canister_id })
+    }
+
+    /// Fetches a verification receipt and checks it against the certified data of the verifier
+    /// canister, so the answer of a single replica doesn't have to be trusted.
+    pub async fn get_certified_receipt(&self, receipt_id: u64) -> anyhow::Result<VerificationReceipt> {
+        let canister = self.0.canister()?;
+        let request = canister
+            .query("get_certified_receipt")
+            .with_arg(receipt_id)
+            .build::<(Result<CertifiedReceipt, verifier::error::ContractError>,)>();
+        let CertifiedReceipt {
+            receipt,
+            certificate,
+            witness,
+        } = request
+            .call()
+            .await?
+            .0
+            .map_err(|e| match e {
+                verifier::error::ContractError::ReceiptNotFound(id) => {
+                    AppError::NotFound(format!("Receipt {} does not exist", id))
+                }
+                e => AppError::Canister(format!("Fail to get certified receipt: {}", e)),
+            })?;
+
+        let canister_id = self.0.canister_id;
+        let certificate: Certificate = serde_cbor::from_slice(&certificate)?;
+        self.0.agent.verify(&certificate, canister_id)?;
+
+        let certified_data = match certificate.tree.lookup_path([
+            "canister".as_bytes(),
+            canister_id.as_slice(),
+            "certified_data".as_bytes(),
+        ]) {
+            LookupResult::Found(data) => data.to_vec(),
+            _ => anyhow::bail!(AppError::Canister(format!(
+                "Certificate doesn't contain the certified data of {}",
+                canister_id
+            ))),
+        };
+
+        let witness: ic_agent::hash_tree::HashTree<Vec<u8>> = serde_cbor::from_slice(&witness)?;
+        if witness.digest().as_slice() != certified_data.as_slice() {
+            anyhow::bail!(AppError::Canister(format!(
+                "Witness of receipt {} doesn't match the certified data",
+                receipt_id
+            )));
+        }
+        match witness.lookup_path([RECEIPTS_LABEL, receipt_key(receipt_id).as_slice()]) {
+            LookupResult::Found(hash) if hash == receipt_hash(&receipt).as_slice() => Ok(receipt),
+            _ => anyhow::bail!(AppError::Canister(format!("Receipt {} is not certified", receipt_id))),
+        }
+    }
+}
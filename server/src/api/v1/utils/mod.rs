@@ -1,2 +1,4 @@
 pub mod ic_caller;
-pub mod calimero_cli;
\ No newline at end of file
+pub mod ic_auth;
+pub mod calimero_client;
+pub mod team_state;
\ No newline at end of file
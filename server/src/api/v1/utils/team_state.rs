@@ -0,0 +1,696 @@
+use crate::api::v1::errors::AppError;
+use crate::api::v1::utils::calimero_client::{CalimeroClient, CalimeroError};
+use crate::games::solver::{is_solved, solve};
+use crate::storage::TeamContext;
+use ic_agent::export::Principal;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use sudoku::game::PlayerContribution;
+use utoipa::ToSchema;
+
+/// Editor address of the cells revealed by the server, see `reveal_cell` in the team application
+pub const HINT_EDITOR: &str = "hint";
+/// Operations read at once from the history of a team
+const HISTORY_PAGE: usize = 500;
+
+/// A cell filled by a member of the team.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeamCell {
+    pub position: u8,
+    pub value: u8,
+    /// Principal of the member, as sent to `set_cell`
+    pub editor_address: String,
+    pub editor_name: String,
+}
+
+/// Share of a member of the team in its reward.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberContribution {
+    #[schema(value_type = String)]
+    pub player: Principal,
+    /// Cells of the solved grid of the team last written by the member
+    pub correct_cells: usize,
+    /// Wrong values of teammates emptied by the member
+    pub removed_cells: usize,
+    /// Between 0 and 1
+    pub percent: f32,
+}
+
+/// A member of a team context, as returned by `get_members`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TeamMember {
+    pub identity: String,
+    /// Principal the member plays the battle with
+    pub address: String,
+    pub name: String,
+    /// `Captain`, `Solver` or `Spectator`
+    pub role: String,
+}
+
+/// A cell emptied by a member of the team, and not undone since.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeamRemoval {
+    pub position: u8,
+    pub editor_address: String,
+    /// Value of the cell before it was emptied
+    pub erased_value: u8,
+    /// Editor of the value erased
+    pub erased_editor: String,
+}
+
+/// An operation of the history of a team grid, as returned by `get_history`.
+#[derive(Debug, Deserialize)]
+struct HistoryOperation {
+    kind: HistoryKind,
+    position: u8,
+    /// (value, editor address, editor name) of the cell before the operation
+    previous: Option<(u8, String, String)>,
+    /// Identity of the member who made the change
+    editor: String,
+    undone: bool,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+enum HistoryKind {
+    Remove,
+    /// Sets, undos, redos and replays
+    #[serde(other)]
+    Other,
+}
+
+/// A proposal to submit the grid which reached the quorum of the team.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FinalizedProposal {
+    pub id: usize,
+    pub public: bool,
+    /// Hex encoded SHA-256 of the 81 values of the grid, 0 for an empty cell
+    pub grid_hash: String,
+}
+
+/// What a team did in its Calimero context: the cells of its grid, the cells its members
+/// emptied and its votes on how to submit it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeamState {
+    pub cells: Vec<TeamCell>,
+    pub removals: Vec<TeamRemoval>,
+    pub finalized_proposal: Option<FinalizedProposal>,
+    /// Votes of the teams using `vote_solution` instead of proposals
+    pub public_votes: Vec<String>,
+    pub private_votes: Vec<String>,
+}
+
+impl TeamState {
+    /// Reads the `BattleState` of a team context as the identity the server joined it with.
+    pub async fn read(calimero: &CalimeroClient, context: &TeamContext) -> Result<Self, CalimeroError> {
+        let cells: Vec<(u8, u8, String, String)> = calimero
+            .call(&context.context_id, &context.context_identity, "get_current_solution", &json!({}))
+            .await?;
+        let mut history: Vec<HistoryOperation> = vec![];
+        loop {
+            let page: Vec<HistoryOperation> = calimero
+                .call(
+                    &context.context_id,
+                    &context.context_identity,
+                    "get_history",
+                    &json!({ "from": history.len(), "limit": HISTORY_PAGE }),
+                )
+                .await?;
+            let last = page.len() < HISTORY_PAGE;
+            history.extend(page);
+            if last {
+                break;
+            }
+        }
+        let finalized_proposal: Option<FinalizedProposal> = calimero
+            .call(&context.context_id, &context.context_identity, "get_finalized_proposal", &json!({}))
+            .await?;
+        let (public_votes, private_votes): (Vec<String>, Vec<String>) = calimero
+            .call(&context.context_id, &context.context_identity, "get_vote_solution", &json!({}))
+            .await?;
+        // The history records identities, the rewards go to the addresses they joined with
+        let addresses: BTreeMap<String, String> = team_members(calimero, context)
+            .await?
+            .into_iter()
+            .map(|member| (member.identity, member.address))
+            .collect();
+        Ok(Self {
+            cells: cells
+                .into_iter()
+                .map(|(position, value, editor_address, editor_name)| TeamCell {
+                    position,
+                    value,
+                    editor_address,
+                    editor_name,
+                })
+                .collect(),
+            removals: history
+                .into_iter()
+                .filter(|op| op.kind == HistoryKind::Remove && !op.undone)
+                .filter_map(|op| {
+                    let (erased_value, erased_editor, _) = op.previous?;
+                    Some(TeamRemoval {
+                        position: op.position,
+                        editor_address: addresses.get(&op.editor)?.clone(),
+                        erased_value,
+                        erased_editor,
+                    })
+                })
+                .collect(),
+            finalized_proposal,
+            public_votes,
+            private_votes,
+        })
+    }
+
+    /// Whether the team voted for a public solution. A finalized proposal must be about the
+    /// grid as it is now. Without proposal, a tie of the votes goes to the private solution like
+    /// in the team UI.
+    pub fn voted_public(&self, initial_state: &[(u8, u8)]) -> Result<bool, AppError> {
+        if let Some(proposal) = &self.finalized_proposal {
+            if proposal.grid_hash != self.grid_hash(initial_state)? {
+                return Err(AppError::Conflict(format!(
+                    "The grid changed since proposal {} was finalized",
+                    proposal.id
+                )));
+            }
+            return Ok(proposal.public);
+        }
+        if self.public_votes.is_empty() && self.private_votes.is_empty() {
+            return Err(AppError::Conflict("The team has not voted on its solution".to_string()));
+        }
+        Ok(self.public_votes.len() > self.private_votes.len())
+    }
+
+    /// Values of the cells left empty by `initial_state`, in grid order, as expected by the
+    /// sudoku canister.
+    pub fn solution(&self, initial_state: &[(u8, u8)]) -> Result<Vec<u8>, AppError> {
+        let grid = self.grid(initial_state)?;
+        let mut solution = vec![];
+        for (position, value) in grid.iter().enumerate() {
+            if is_given(initial_state, position as u8) {
+                continue;
+            }
+            solution.push(value.ok_or(AppError::Validation(format!("Cell {} is empty", position)))?);
+        }
+        Ok(solution)
+    }
+
+    /// Share of each member in the reward, once the grid of the team is solved: each cell of the
+    /// grid counts for its last editor and emptying a wrong value of a teammate for
+    /// `removal_weight` of a cell. The grid is checked against the rules of sudoku rather than
+    /// a solution of the puzzle, which may have several. A wrong value only earns its first
+    /// remover, so undoing and emptying it again earns nothing.
+    pub fn member_contributions(
+        &self,
+        initial_state: &[(u8, u8)],
+        removal_weight: f32,
+    ) -> Result<Vec<MemberContribution>, AppError> {
+        let solved = self.solved_grid(initial_state)?;
+
+        let mut members = BTreeMap::<&str, (usize, usize)>::new();
+        // Revealed cells count for nobody
+        for cell in self.filled_cells(initial_state).filter(|cell| cell.editor_address != HINT_EDITOR) {
+            members.entry(&cell.editor_address).or_default().0 += 1;
+        }
+        let mut fixed = BTreeSet::new();
+        for removal in &self.removals {
+            if removal.position >= 81
+                || is_given(initial_state, removal.position)
+                || removal.erased_editor == removal.editor_address
+                || removal.erased_value == solved[removal.position as usize]
+            {
+                continue;
+            }
+            if fixed.insert((removal.position, removal.erased_value, &removal.erased_editor)) {
+                members.entry(&removal.editor_address).or_default().1 += 1;
+            }
+        }
+
+        let score = |&(correct_cells, removed_cells): &(usize, usize)| {
+            correct_cells as f32 + removed_cells as f32 * removal_weight
+        };
+        let total: f32 = members.values().map(score).sum();
+        if total <= 0.0 {
+            return Err(AppError::Validation("No member of the team filled a cell".to_string()));
+        }
+        members
+            .into_iter()
+            .filter(|(_, counts)| score(counts) > 0.0)
+            .map(|(editor, counts)| {
+                Ok(MemberContribution {
+                    player: Principal::from_str(editor).map_err(|_| {
+                        AppError::Validation(format!("Cell editor {} is not a principal", editor))
+                    })?,
+                    correct_cells: counts.0,
+                    removed_cells: counts.1,
+                    percent: score(&counts) / total,
+                })
+            })
+            .collect()
+    }
+
+    /// The cell to reveal as a hint with its value in a solution of the puzzle which keeps the
+    /// cells of the team: `position` if set, otherwise the first cell of the grid which is empty
+    /// or wrong.
+    pub fn hint(&self, initial_state: &[(u8, u8)], position: Option<u8>) -> Result<(u8, u8), AppError> {
+        let grid = self.grid(initial_state)?;
+        let answer = self.answer(initial_state)?;
+        let position = match position {
+            Some(position) if position >= 81 => {
+                return Err(AppError::Validation(format!("Cell {} is outside of the grid", position)))
+            }
+            Some(position) if is_given(initial_state, position) => {
+                return Err(AppError::Validation(format!("Cell {} is given by the puzzle", position)))
+            }
+            Some(position) if grid[position as usize] == Some(answer[position as usize]) => {
+                return Err(AppError::Conflict(format!("Cell {} is already correct", position)))
+            }
+            Some(position) => position,
+            None => (0..81)
+                .find(|&position| grid[position as usize] != Some(answer[position as usize]))
+                .ok_or(AppError::Conflict("The grid of the team is already solved".to_string()))?,
+        };
+        Ok((position, answer[position as usize]))
+    }
+
+    /// [`Self::member_contributions`] as submitted to the sudoku canister.
+    pub fn contributions(
+        &self,
+        initial_state: &[(u8, u8)],
+        removal_weight: f32,
+    ) -> Result<Vec<PlayerContribution>, AppError> {
+        Ok(self
+            .member_contributions(initial_state, removal_weight)?
+            .into_iter()
+            .map(|c| PlayerContribution {
+                player: c.player,
+                percent: c.percent,
+            })
+            .collect())
+    }
+
+    /// The cells of the team outside of the givens, which the team cannot change.
+    fn filled_cells<'a>(&'a self, initial_state: &'a [(u8, u8)]) -> impl Iterator<Item = &'a TeamCell> {
+        self.cells
+            .iter()
+            .filter(|cell| !is_given(initial_state, cell.position))
+    }
+
+    /// A solution of the puzzle keeping as many cells of the team as possible, in grid order: a
+    /// cell is kept when the puzzle can still be solved with it and the cells kept before. The
+    /// puzzle may have several solutions, the one the team heads for is the right one.
+    fn answer(&self, initial_state: &[(u8, u8)]) -> Result<[u8; 81], AppError> {
+        let grid = self.grid(initial_state)?;
+        let mut answer = solve(initial_state)
+            .ok_or(AppError::Conflict("The puzzle of the battle has no solution".to_string()))?;
+        let mut kept = initial_state.to_vec();
+        for (position, value) in grid.iter().enumerate() {
+            let Some(value) = *value else {
+                continue;
+            };
+            if is_given(initial_state, position as u8) {
+                continue;
+            }
+            kept.push((position as u8, value));
+            // The current answer already holds every cell kept so far
+            if answer[position] == value {
+                continue;
+            }
+            match solve(&kept) {
+                Some(found) => answer = found,
+                None => {
+                    kept.pop();
+                }
+            }
+        }
+        Ok(answer)
+    }
+
+    /// The grid of the team, if it is complete and repeats no value in a row, a column or a box.
+    fn solved_grid(&self, initial_state: &[(u8, u8)]) -> Result<[u8; 81], AppError> {
+        let grid = self.grid(initial_state)?;
+        let mut solved = [0; 81];
+        for (position, value) in grid.iter().enumerate() {
+            solved[position] = value.ok_or(AppError::Validation(format!("Cell {} is empty", position)))?;
+        }
+        if !is_solved(&solved) {
+            return Err(AppError::Validation("The grid of the team breaks the rules of sudoku".to_string()));
+        }
+        Ok(solved)
+    }
+
+    fn grid_hash(&self, initial_state: &[(u8, u8)]) -> Result<String, AppError> {
+        let grid = self.grid(initial_state)?.map(|value| value.unwrap_or_default());
+        Ok(hex::encode(Sha256::digest(grid)))
+    }
+
+    fn grid(&self, initial_state: &[(u8, u8)]) -> Result<[Option<u8>; 81], AppError> {
+        let mut grid = [None; 81];
+        for &(position, value) in initial_state {
+            grid[position as usize] = Some(value);
+        }
+        for cell in self.filled_cells(initial_state) {
+            if cell.position >= 81 || !(1..=9).contains(&cell.value) {
+                return Err(AppError::Validation(format!(
+                    "Cell {} of the team has an invalid value {}",
+                    cell.position, cell.value
+                )));
+            }
+            grid[cell.position as usize] = Some(cell.value);
+        }
+        Ok(grid)
+    }
+}
+
+/// Gives the puzzle of a battle to a team context, its members cannot edit the givens.
+pub async fn set_team_puzzle(
+    calimero: &CalimeroClient,
+    context: &TeamContext,
+    initial_state: &[(u8, u8)],
+) -> Result<(), CalimeroError> {
+    calimero
+        .call(
+            &context.context_id,
+            &context.context_identity,
+            "set_initial_state",
+            &json!({ "initial_state": initial_state }),
+        )
+        .await
+}
+
+/// Lets the player of `address` join a team context with a token hashed into `token_hash`.
+pub async fn admit_member(
+    calimero: &CalimeroClient,
+    context: &TeamContext,
+    address: &str,
+    token_hash: &str,
+) -> Result<(), CalimeroError> {
+    calimero
+        .call(
+            &context.context_id,
+            &context.context_identity,
+            "admit",
+            &json!({ "address": address, "token_hash": token_hash }),
+        )
+        .await
+}
+
+/// Members of a team context.
+pub async fn team_members(calimero: &CalimeroClient, context: &TeamContext) -> Result<Vec<TeamMember>, CalimeroError> {
+    calimero
+        .call(&context.context_id, &context.context_identity, "get_members", &json!({}))
+        .await
+}
+
+/// Writes the value of a cell of the solution into a team context, as the server which created
+/// it.
+pub async fn reveal_cell(
+    calimero: &CalimeroClient,
+    context: &TeamContext,
+    position: u8,
+    value: u8,
+) -> Result<(), CalimeroError> {
+    let _version: u64 = calimero
+        .call(
+            &context.context_id,
+            &context.context_identity,
+            "reveal_cell",
+            &json!({ "position": position, "value": value }),
+        )
+        .await?;
+    Ok(())
+}
+
+fn is_given(initial_state: &[(u8, u8)], position: u8) -> bool {
+    initial_state.iter().any(|&(cell, _)| cell == position)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::v1::utils::team_state::{
+        FinalizedProposal, HistoryKind, HistoryOperation, MemberContribution, TeamCell, TeamRemoval, TeamState,
+        HINT_EDITOR,
+    };
+    use ic_agent::export::Principal;
+    use sha2::{Digest, Sha256};
+
+    const SOLVED: &str = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+    /// Cells of [`SOLVED`] whose values can be swapped as 7, 6, 6, 7 without breaking the rules
+    const SWAPPABLE: [u8; 4] = [3, 4, 30, 31];
+
+    /// The first `count` cells of [`SOLVED`] as givens, the puzzle having a single solution.
+    fn givens(count: usize) -> Vec<(u8, u8)> {
+        SOLVED.bytes().take(count).enumerate().map(|(cell, c)| (cell as u8, c - b'0')).collect()
+    }
+
+    /// Every cell of [`SOLVED`] but the [`SWAPPABLE`] ones, the puzzle having two solutions.
+    fn two_solutions() -> Vec<(u8, u8)> {
+        givens(81).into_iter().filter(|(cell, _)| !SWAPPABLE.contains(cell)).collect()
+    }
+
+    fn team(cells: Vec<TeamCell>) -> TeamState {
+        TeamState {
+            cells,
+            removals: vec![],
+            finalized_proposal: None,
+            public_votes: vec![],
+            private_votes: vec![],
+        }
+    }
+
+    fn cell(position: u8, value: u8, editor: Principal) -> TeamCell {
+        TeamCell {
+            position,
+            value,
+            editor_address: editor.to_text(),
+            editor_name: "player".to_string(),
+        }
+    }
+
+    fn revealed(position: u8, value: u8) -> TeamCell {
+        TeamCell {
+            position,
+            value,
+            editor_address: HINT_EDITOR.to_string(),
+            editor_name: "Hint".to_string(),
+        }
+    }
+
+    fn removal(position: u8, editor: Principal, erased_value: u8, erased_editor: Principal) -> TeamRemoval {
+        TeamRemoval {
+            position,
+            editor_address: editor.to_text(),
+            erased_value,
+            erased_editor: erased_editor.to_text(),
+        }
+    }
+
+    #[test]
+    fn test_team_state() {
+        let alice = Principal::self_authenticating(b"alice");
+        let bob = Principal::self_authenticating(b"bob");
+        let initial_state = givens(77);
+        let mut state = TeamState {
+            public_votes: vec![alice.to_text()],
+            private_votes: vec![bob.to_text()],
+            ..team(vec![
+                cell(80, 9, bob),
+                cell(77, 6, alice),
+                // overwriting a given is ignored
+                cell(0, 4, bob),
+                cell(78, 1, alice),
+                cell(79, 7, alice),
+            ])
+        };
+
+        assert_eq!(state.solution(&initial_state).unwrap(), vec![6, 1, 7, 9]);
+        let contributions = state.contributions(&initial_state, 0.5).unwrap();
+        assert_eq!(contributions.len(), 2);
+        for c in contributions {
+            assert_eq!(c.percent, if c.player == alice { 0.75 } else { 0.25 });
+        }
+        assert!(!state.voted_public(&initial_state).unwrap());
+
+        state.cells[4] = cell(79, 10, alice);
+        assert!(state.solution(&initial_state).is_err());
+        state.cells[4] = TeamCell {
+            editor_address: "alice".to_string(),
+            ..cell(79, 7, alice)
+        };
+        assert!(state.contributions(&initial_state, 0.5).is_err());
+        state.cells.pop();
+        assert!(state.solution(&initial_state).is_err());
+
+        state.private_votes.clear();
+        assert!(state.voted_public(&initial_state).unwrap());
+        state.public_votes.clear();
+        assert!(state.voted_public(&initial_state).is_err());
+    }
+
+    #[test]
+    fn test_finalized_proposal() {
+        let alice = Principal::self_authenticating(b"alice");
+        let initial_state = givens(80);
+        let mut state = TeamState {
+            finalized_proposal: Some(FinalizedProposal {
+                id: 0,
+                public: true,
+                // same hash as the app, which hashes the 81 values of the grid
+                grid_hash: hex::encode(Sha256::digest(SOLVED.bytes().map(|c| c - b'0').collect::<Vec<_>>())),
+            }),
+            // the proposal wins over the votes
+            private_votes: vec![alice.to_text()],
+            ..team(vec![cell(80, 9, alice)])
+        };
+        assert!(state.voted_public(&initial_state).unwrap());
+
+        state.cells[0].value = 8;
+        assert!(state.voted_public(&initial_state).is_err());
+    }
+
+    #[test]
+    fn test_member_contributions() {
+        let alice = Principal::self_authenticating(b"alice");
+        let bob = Principal::self_authenticating(b"bob");
+        let carol = Principal::self_authenticating(b"carol");
+        let initial_state = givens(77);
+        let mut state = TeamState {
+            removals: vec![
+                // carol's wrong 5, emptied again after an undo, earns bob a single removal
+                removal(79, bob, 5, carol),
+                removal(79, bob, 5, carol),
+                // emptying a correct value, its own value or a given earns nothing
+                removal(79, alice, 7, bob),
+                removal(80, bob, 4, bob),
+                removal(3, alice, 5, bob),
+            ],
+            ..team(vec![cell(77, 6, alice), cell(78, 1, alice), revealed(79, 7), revealed(80, 9)])
+        };
+
+        let contributions = state.member_contributions(&initial_state, 0.5).unwrap();
+        let contribution = |player| contributions.iter().find(|c| c.player == player).unwrap();
+        assert_eq!(
+            contribution(alice),
+            &MemberContribution {
+                player: alice,
+                correct_cells: 2,
+                removed_cells: 0,
+                percent: 0.8,
+            }
+        );
+        assert_eq!(
+            contribution(bob),
+            &MemberContribution {
+                player: bob,
+                correct_cells: 0,
+                removed_cells: 1,
+                percent: 0.2,
+            }
+        );
+
+        // without weight, removals earn nothing
+        let contributions = state.member_contributions(&initial_state, 0.0).unwrap();
+        assert_eq!(contributions.len(), 1);
+        assert_eq!(contributions[0].player, alice);
+        assert_eq!(contributions[0].percent, 1.0);
+
+        // the grid must be solved first
+        state.cells[3] = cell(80, 8, bob);
+        assert!(state.member_contributions(&initial_state, 0.5).is_err());
+        state.cells.pop();
+        assert!(state.member_contributions(&initial_state, 0.5).is_err());
+    }
+
+    #[test]
+    fn test_two_solutions() {
+        let alice = Principal::self_authenticating(b"alice");
+        let bob = Principal::self_authenticating(b"bob");
+        let initial_state = two_solutions();
+        // the solution which is not SOLVED, where the solver finds the other one
+        let state = TeamState {
+            // the 6 alice emptied is wrong in the grid of the team
+            removals: vec![removal(3, bob, 6, alice)],
+            ..team(vec![cell(3, 7, alice), cell(4, 6, alice), cell(30, 6, bob), cell(31, 7, bob)])
+        };
+
+        let contributions = state.member_contributions(&initial_state, 1.0).unwrap();
+        assert_eq!(contributions.len(), 2);
+        for c in contributions {
+            assert_eq!(c.correct_cells, 2);
+            assert_eq!(c.removed_cells, if c.player == bob { 1 } else { 0 });
+        }
+    }
+
+    #[test]
+    fn test_hint() {
+        let alice = Principal::self_authenticating(b"alice");
+        let initial_state = givens(77);
+        // alice's 2 is wrong
+        let mut state = team(vec![cell(77, 6, alice), cell(78, 2, alice)]);
+
+        assert_eq!(state.hint(&initial_state, None).unwrap(), (78, 1));
+        assert_eq!(state.hint(&initial_state, Some(80)).unwrap(), (80, 9));
+        assert!(state.hint(&initial_state, Some(77)).is_err());
+        assert!(state.hint(&initial_state, Some(3)).is_err());
+        assert!(state.hint(&initial_state, Some(81)).is_err());
+
+        // a revealed cell counts for nobody
+        state.cells[1] = revealed(78, 1);
+        state.cells.extend([cell(79, 7, alice), cell(80, 9, alice)]);
+        let contributions = state.member_contributions(&initial_state, 0.5).unwrap();
+        assert_eq!(contributions.len(), 1);
+        assert_eq!(contributions[0].correct_cells, 3);
+        assert!(state.hint(&initial_state, None).is_err());
+    }
+
+    #[test]
+    fn test_hint_two_solutions() {
+        let alice = Principal::self_authenticating(b"alice");
+        let initial_state = two_solutions();
+        assert_eq!(team(vec![]).hint(&initial_state, None).unwrap(), (3, 6));
+
+        // the hints follow the solution the team chose
+        let mut state = team(vec![cell(3, 7, alice)]);
+        assert_eq!(state.hint(&initial_state, None).unwrap(), (4, 6));
+        assert_eq!(state.hint(&initial_state, Some(31)).unwrap(), (31, 7));
+        assert!(state.hint(&initial_state, Some(3)).is_err());
+
+        // a cell which fits no solution with the ones before is revealed
+        state.cells.push(cell(4, 7, alice));
+        assert_eq!(state.hint(&initial_state, None).unwrap(), (4, 6));
+    }
+
+    #[test]
+    fn test_history_operation() {
+        let remove: HistoryOperation = serde_json::from_value(serde_json::json!({
+            "kind": {"type": "Remove"},
+            "position": 4,
+            "previous": [5, "alice", "Alice"],
+            "value": null,
+            "editor": "bob-identity",
+            "time": 1,
+            "version": 2,
+            "undone": false,
+        }))
+        .unwrap();
+        assert_eq!(remove.kind, HistoryKind::Remove);
+        assert_eq!(remove.previous, Some((5, "alice".to_string(), "Alice".to_string())));
+        let undo: HistoryOperation = serde_json::from_value(serde_json::json!({
+            "kind": {"type": "Undo", "index": 3},
+            "position": 4,
+            "previous": null,
+            "value": [5, "alice", "Alice"],
+            "editor": "bob-identity",
+            "time": 1,
+            "version": 3,
+            "undone": false,
+        }))
+        .unwrap();
+        assert_eq!(undo.kind, HistoryKind::Other);
+    }
+}
@@ -10,12 +10,39 @@ pub struct Env {
     pub RPC_URL: String,
     pub SERVICE_FEE: u128,
     pub APPLICATION_ID: String,
+    /// Admin API of the Calimero node running the team contexts
+    pub CALIMERO_NODE_URL: String,
+    /// `local` for a local replica, whose root key is fetched at startup, or `ic` for mainnet, the
+    /// default so a deploy never trusts a root key it fetched by mistake
+    pub IC_NETWORK: String,
+    /// SQLite database holding teams, battle mirrors, proof jobs and submissions
+    pub DATABASE_URL: String,
+    /// Number of proofs generated at the same time
+    pub PROOF_WORKERS: usize,
+    /// Proof jobs queued or running at the same time, new ones are refused beyond it
+    pub PROOF_QUEUE_CAPACITY: usize,
+    /// Proof jobs a single caller can have queued or running
+    pub PROOF_JOBS_PER_OWNER: usize,
+    /// Seconds between two refreshes of the battles from the canister
+    pub BATTLE_POLL_INTERVAL: u64,
+    /// Seconds a signed request stays valid around its timestamp
+    pub AUTH_MAX_CLOCK_SKEW: u64,
+    /// Share of a correct cell a team member earns for emptying a cell
+    pub CONTRIBUTION_REMOVAL_WEIGHT: f32,
+    /// Hints each team can take in a new battle
+    pub HINT_BUDGET: u32,
+    /// Share of the prize pool a team loses for each hint it took
+    pub HINT_PENALTY: f32,
 }
 
 static ENV: OnceCell<Env> = OnceCell::const_new();
 
 fn read_env(key: &'static str) -> String {
-    std::env::var(key).expect(&format!("{} must be set", key))
+    std::env::var(key).unwrap_or_else(|_| panic!("{} must be set", key))
+}
+
+fn read_env_or(key: &'static str, default: &str) -> String {
+    std::env::var(key).unwrap_or_else(|_| default.to_string())
 }
 
 pub async fn config_env() {
@@ -29,6 +56,17 @@ pub async fn config_env() {
             RPC_URL: read_env("RPC_URL"),
             SERVICE_FEE: read_env("SERVICE_FEE").parse().unwrap(),
             APPLICATION_ID: read_env("APPLICATION_ID"),
+            CALIMERO_NODE_URL: read_env_or("CALIMERO_NODE_URL", "http://127.0.0.1:2428"),
+            IC_NETWORK: read_env_or("IC_NETWORK", "ic"),
+            DATABASE_URL: read_env_or("DATABASE_URL", "sqlite://cali-moba.db"),
+            PROOF_WORKERS: read_env_or("PROOF_WORKERS", "1").parse().unwrap(),
+            PROOF_QUEUE_CAPACITY: read_env_or("PROOF_QUEUE_CAPACITY", "32").parse().unwrap(),
+            PROOF_JOBS_PER_OWNER: read_env_or("PROOF_JOBS_PER_OWNER", "2").parse().unwrap(),
+            BATTLE_POLL_INTERVAL: read_env_or("BATTLE_POLL_INTERVAL", "5").parse().unwrap(),
+            AUTH_MAX_CLOCK_SKEW: read_env_or("AUTH_MAX_CLOCK_SKEW", "300").parse().unwrap(),
+            CONTRIBUTION_REMOVAL_WEIGHT: read_env_or("CONTRIBUTION_REMOVAL_WEIGHT", "0.25").parse().unwrap(),
+            HINT_BUDGET: read_env_or("HINT_BUDGET", "3").parse().unwrap(),
+            HINT_PENALTY: read_env_or("HINT_PENALTY", "0.1").parse().unwrap(),
         }
     }).await;
 }
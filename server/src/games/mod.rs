@@ -1,5 +1,6 @@
 use sp1_sdk::SP1ProofWithPublicValues;
 
+pub mod solver;
 pub mod sudoku;
 
 #[async_trait::async_trait]
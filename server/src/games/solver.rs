@@ -0,0 +1,85 @@
+/// Solves a sudoku given as `(cell, value)` pairs, returns the first solution found by
+/// backtracking on the cell with the fewest candidates.
+pub fn solve(initial_state: &[(u8, u8)]) -> Option<[u8; 81]> {
+    let mut grid = [0; 81];
+    for &(cell, value) in initial_state {
+        let cell = cell as usize;
+        if cell >= 81 || !(1..=9).contains(&value) || candidates(&grid, cell) & (1 << value) == 0 {
+            return None;
+        }
+        grid[cell] = value;
+    }
+    fill(&mut grid).then_some(grid)
+}
+
+/// Whether `grid` is complete and repeats no value in a row, a column or a box.
+pub fn is_solved(grid: &[u8; 81]) -> bool {
+    let cells: Vec<(u8, u8)> = grid.iter().enumerate().map(|(cell, &value)| (cell as u8, value)).collect();
+    solve(&cells).is_some()
+}
+
+fn fill(grid: &mut [u8; 81]) -> bool {
+    // The empty cell with the fewest candidates keeps the search small
+    let Some((cell, candidates)) = (0..81)
+        .filter(|&cell| grid[cell] == 0)
+        .map(|cell| (cell, candidates(grid, cell)))
+        .min_by_key(|(_, candidates)| candidates.count_ones())
+    else {
+        return true;
+    };
+    for value in 1..=9 {
+        if candidates & (1 << value) != 0 {
+            grid[cell] = value;
+            if fill(grid) {
+                return true;
+            }
+        }
+    }
+    grid[cell] = 0;
+    false
+}
+
+/// Bit `v` is set when `v` can be written in `cell` without repeating a value of its row,
+/// column or box.
+fn candidates(grid: &[u8; 81], cell: usize) -> u16 {
+    let (row, col) = (cell / 9, cell % 9);
+    let (box_row, box_col) = (row / 3 * 3, col / 3 * 3);
+    let mut used = 0u16;
+    for i in 0..9 {
+        used |= 1 << grid[row * 9 + i];
+        used |= 1 << grid[i * 9 + col];
+        used |= 1 << grid[(box_row + i / 3) * 9 + box_col + i % 3];
+    }
+    !used & 0b11_1111_1110
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::games::solver::{is_solved, solve};
+
+    #[test]
+    fn test_solve() {
+        let puzzle = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let initial_state: Vec<(u8, u8)> = puzzle
+            .chars()
+            .enumerate()
+            .filter_map(|(cell, c)| c.to_digit(10).map(|value| (cell as u8, value as u8)))
+            .collect();
+        let solution = solve(&initial_state).unwrap();
+        let expected = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+        assert_eq!(
+            solution.iter().map(|value| value.to_string()).collect::<String>(),
+            expected
+        );
+
+        assert!(solve(&[(0, 5), (1, 5)]).is_none());
+        assert!(solve(&[(81, 5)]).is_none());
+
+        assert!(is_solved(&solution));
+        let mut grid = solution;
+        grid.swap(0, 1);
+        assert!(!is_solved(&grid));
+        grid[0] = 0;
+        assert!(!is_solved(&grid));
+    }
+}
@@ -1,15 +1,27 @@
+use crate::api::v1::app_state::AppState;
+use crate::api::v1::battle_poller::start_battle_poller;
+use crate::api::v1::proof_queue::ProofQueue;
 use crate::api::v1::routes::all_routes::get_all_routes;
 use crate::config::config_app;
 use crate::config::env_config::env;
+use std::time::Duration;
 
 pub mod games;
 pub mod api;
 pub mod config;
+pub mod storage;
 
 #[tokio::main]
 async fn main() {
     config_app().await;
-    let app = get_all_routes();
+    let state = AppState::from_env(env())
+        .await
+        .expect("failed to connect to the replica");
+    ProofQueue::start(state.clone(), env().PROOF_WORKERS)
+        .await
+        .expect("failed to start the proof workers");
+    start_battle_poller(state.clone(), Duration::from_secs(env().BATTLE_POLL_INTERVAL));
+    let app = get_all_routes(state);
     let listener =
         tokio::net::TcpListener::bind(format!("{}:{}", env().SERVER_HOST, env().SERVER_PORT))
             .await
@@ -0,0 +1,222 @@
+pub mod sqlite;
+
+use async_trait::async_trait;
+use ic_agent::export::Principal;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use sudoku::game::{PlayerContribution, SudokuGame};
+use utoipa::ToSchema;
+
+/// A team and the Calimero context its members play in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Team {
+    pub id: i64,
+    pub battle_id: Option<usize>,
+    /// Caller which created the team, allowed to invite before joining the team's context
+    pub creator: Option<Principal>,
+    pub context: TeamContext,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamContext {
+    pub context_id: String,
+    pub context_identity: String,
+    pub application_id: String,
+}
+
+/// Battle metadata mirrored from the sudoku canister, which stays the source of truth.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BattleRecord {
+    pub id: usize,
+    pub creator: Principal,
+    pub deposit_price: u128,
+    pub service_fee: u128,
+    pub players: Vec<Principal>,
+    pub initial_state: Option<Vec<(u8, u8)>>,
+    pub finished: bool,
+    pub updated_at: i64,
+}
+
+impl BattleRecord {
+    pub fn from_game(id: usize, game: &SudokuGame) -> Self {
+        Self {
+            id,
+            creator: game.creator,
+            deposit_price: game.deposit_price,
+            service_fee: game.service_fee,
+            players: game.players.clone(),
+            initial_state: game.initial_state.clone(),
+            finished: game.solution.is_some(),
+            updated_at: now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ProofJobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl ProofJobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProofJobStatus::Queued => "queued",
+            ProofJobStatus::Running => "running",
+            ProofJobStatus::Succeeded => "succeeded",
+            ProofJobStatus::Failed => "failed",
+            ProofJobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(status: &str) -> anyhow::Result<Self> {
+        Ok(match status {
+            "queued" => ProofJobStatus::Queued,
+            "running" => ProofJobStatus::Running,
+            "succeeded" => ProofJobStatus::Succeeded,
+            "failed" => ProofJobStatus::Failed,
+            "cancelled" => ProofJobStatus::Cancelled,
+            _ => anyhow::bail!("Unknown proof job status: {}", status),
+        })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, ProofJobStatus::Queued | ProofJobStatus::Running)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofJob {
+    pub id: String,
+    pub battle_id: Option<usize>,
+    /// Caller which requested the proof, the only one allowed to read or cancel it along with
+    /// the contributors of the submission waiting for it
+    pub owner: Option<Principal>,
+    pub status: ProofJobStatus,
+    pub initial_state: Vec<(u8, u8)>,
+    pub solution: Vec<u8>,
+    /// Hex encoded proof bytes, set once the job succeeded
+    pub proof: Option<String>,
+    /// Hex encoded public values, set once the job succeeded
+    pub public_values: Option<String>,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl ProofJob {
+    pub fn new(
+        battle_id: Option<usize>,
+        owner: Principal,
+        initial_state: Vec<(u8, u8)>,
+        solution: Vec<u8>,
+    ) -> Self {
+        let created_at = now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            battle_id,
+            owner: Some(owner),
+            status: ProofJobStatus::Queued,
+            initial_state,
+            solution,
+            proof: None,
+            public_values: None,
+            error: None,
+            created_at,
+            updated_at: created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Submission {
+    pub id: i64,
+    pub battle_id: usize,
+    pub team_id: Option<i64>,
+    pub public: bool,
+    pub proof_job_id: Option<String>,
+    pub player_contributions: Vec<PlayerContribution>,
+    pub status: SubmissionStatus,
+    pub error: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SubmissionStatus {
+    /// Waiting for its proof job
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+impl SubmissionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubmissionStatus::Pending => "pending",
+            SubmissionStatus::Accepted => "accepted",
+            SubmissionStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn parse(status: &str) -> anyhow::Result<Self> {
+        Ok(match status {
+            "pending" => SubmissionStatus::Pending,
+            "accepted" => SubmissionStatus::Accepted,
+            "rejected" => SubmissionStatus::Rejected,
+            _ => anyhow::bail!("Unknown submission status: {}", status),
+        })
+    }
+}
+
+/// Persistence used by the server for everything it does not read from the canisters.
+///
+/// [`sqlite::SqliteStorage`] is the default implementation; other databases only need to
+/// implement this trait.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn create_team(
+        &self,
+        battle_id: Option<usize>,
+        creator: Principal,
+        context: TeamContext,
+    ) -> anyhow::Result<Team>;
+    async fn get_team(&self, id: i64) -> anyhow::Result<Option<Team>>;
+    async fn get_team_by_context(&self, context_id: &str) -> anyhow::Result<Option<Team>>;
+    async fn list_teams(&self, battle_id: usize) -> anyhow::Result<Vec<Team>>;
+
+    async fn upsert_battle(&self, battle: &BattleRecord) -> anyhow::Result<()>;
+    async fn get_battle(&self, id: usize) -> anyhow::Result<Option<BattleRecord>>;
+    async fn list_unfinished_battles(&self) -> anyhow::Result<Vec<BattleRecord>>;
+    /// Highest battle id mirrored so far
+    async fn last_battle_id(&self) -> anyhow::Result<Option<usize>>;
+
+    async fn insert_proof_job(&self, job: &ProofJob) -> anyhow::Result<()>;
+    async fn update_proof_job(&self, job: &ProofJob) -> anyhow::Result<()>;
+    async fn get_proof_job(&self, id: &str) -> anyhow::Result<Option<ProofJob>>;
+    /// Queued and running jobs, oldest first
+    async fn list_unfinished_proof_jobs(&self) -> anyhow::Result<Vec<ProofJob>>;
+
+    async fn insert_submission(&self, submission: &Submission) -> anyhow::Result<i64>;
+    async fn update_submission(&self, submission: &Submission) -> anyhow::Result<()>;
+    async fn get_submission_by_proof_job(&self, proof_job_id: &str) -> anyhow::Result<Option<Submission>>;
+    async fn list_submissions(&self, battle_id: usize) -> anyhow::Result<Vec<Submission>>;
+}
+
+/// Current unix time in seconds
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,437 @@
+use crate::storage::{
+    now, BattleRecord, ProofJob, ProofJobStatus, Storage, Submission, SubmissionStatus, Team,
+    TeamContext,
+};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ic_agent::export::Principal;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow};
+use sqlx::{Row, SqlitePool};
+use std::str::FromStr;
+
+pub struct SqliteStorage {
+    pool: SqlitePool,
+}
+
+impl SqliteStorage {
+    /// Opens the database at `url`, creating it if needed, and runs the pending migrations.
+    pub async fn connect(url: &str) -> anyhow::Result<Self> {
+        let options = SqliteConnectOptions::from_str(url)?
+            .create_if_missing(true)
+            .foreign_keys(true);
+        // every connection to an in-memory database gets its own empty database
+        let max_connections = if url.contains(":memory:") { 1 } else { 5 };
+        let pool = SqlitePoolOptions::new()
+            .max_connections(max_connections)
+            .connect_with(options)
+            .await
+            .map_err(|e| anyhow!("Fail to open database {}: {:?}", url, e))?;
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .map_err(|e| anyhow!("Fail to run migrations: {:?}", e))?;
+        Ok(Self { pool })
+    }
+}
+
+const TEAM_COLUMNS: &str = "teams.id, teams.battle_id, teams.creator, teams.created_at, team_contexts.context_id, \
+    team_contexts.context_identity, team_contexts.application_id \
+    FROM teams JOIN team_contexts ON team_contexts.team_id = teams.id";
+
+fn team_from_row(row: &SqliteRow) -> anyhow::Result<Team> {
+    Ok(Team {
+        id: row.try_get("id")?,
+        battle_id: row.try_get::<Option<i64>, _>("battle_id")?.map(|id| id as usize),
+        creator: row
+            .try_get::<Option<&str>, _>("creator")?
+            .map(Principal::from_text)
+            .transpose()?,
+        context: TeamContext {
+            context_id: row.try_get("context_id")?,
+            context_identity: row.try_get("context_identity")?,
+            application_id: row.try_get("application_id")?,
+        },
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+fn battle_from_row(row: &SqliteRow) -> anyhow::Result<BattleRecord> {
+    let players: Vec<String> = serde_json::from_str(row.try_get("players")?)?;
+    Ok(BattleRecord {
+        id: row.try_get::<i64, _>("id")? as usize,
+        creator: Principal::from_text(row.try_get::<&str, _>("creator")?)?,
+        deposit_price: row.try_get::<&str, _>("deposit_price")?.parse()?,
+        service_fee: row.try_get::<&str, _>("service_fee")?.parse()?,
+        players: players
+            .iter()
+            .map(Principal::from_text)
+            .collect::<Result<_, _>>()?,
+        initial_state: row
+            .try_get::<Option<&str>, _>("initial_state")?
+            .map(serde_json::from_str)
+            .transpose()?,
+        finished: row.try_get("finished")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+fn proof_job_from_row(row: &SqliteRow) -> anyhow::Result<ProofJob> {
+    Ok(ProofJob {
+        id: row.try_get("id")?,
+        battle_id: row.try_get::<Option<i64>, _>("battle_id")?.map(|id| id as usize),
+        owner: row
+            .try_get::<Option<&str>, _>("owner")?
+            .map(Principal::from_text)
+            .transpose()?,
+        status: ProofJobStatus::parse(row.try_get("status")?)?,
+        initial_state: serde_json::from_str(row.try_get("initial_state")?)?,
+        solution: serde_json::from_str(row.try_get("solution")?)?,
+        proof: row.try_get("proof")?,
+        public_values: row.try_get("public_values")?,
+        error: row.try_get("error")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+fn submission_from_row(row: &SqliteRow) -> anyhow::Result<Submission> {
+    Ok(Submission {
+        id: row.try_get("id")?,
+        battle_id: row.try_get::<i64, _>("battle_id")? as usize,
+        team_id: row.try_get("team_id")?,
+        public: row.try_get("public")?,
+        proof_job_id: row.try_get("proof_job_id")?,
+        player_contributions: serde_json::from_str(row.try_get("player_contributions")?)?,
+        status: SubmissionStatus::parse(row.try_get("status")?)?,
+        error: row.try_get("error")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn create_team(
+        &self,
+        battle_id: Option<usize>,
+        creator: Principal,
+        context: TeamContext,
+    ) -> anyhow::Result<Team> {
+        let created_at = now();
+        let mut tx = self.pool.begin().await?;
+        let id = sqlx::query("INSERT INTO teams (battle_id, creator, created_at) VALUES (?, ?, ?)")
+            .bind(battle_id.map(|id| id as i64))
+            .bind(creator.to_text())
+            .bind(created_at)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid();
+        sqlx::query(
+            "INSERT INTO team_contexts (context_id, team_id, context_identity, application_id) \
+            VALUES (?, ?, ?, ?)",
+        )
+        .bind(&context.context_id)
+        .bind(id)
+        .bind(&context.context_identity)
+        .bind(&context.application_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(Team {
+            id,
+            battle_id,
+            creator: Some(creator),
+            context,
+            created_at,
+        })
+    }
+
+    async fn get_team(&self, id: i64) -> anyhow::Result<Option<Team>> {
+        sqlx::query(&format!("SELECT {TEAM_COLUMNS} WHERE teams.id = ?"))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .as_ref()
+            .map(team_from_row)
+            .transpose()
+    }
+
+    async fn get_team_by_context(&self, context_id: &str) -> anyhow::Result<Option<Team>> {
+        sqlx::query(&format!("SELECT {TEAM_COLUMNS} WHERE team_contexts.context_id = ?"))
+            .bind(context_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .as_ref()
+            .map(team_from_row)
+            .transpose()
+    }
+
+    async fn list_teams(&self, battle_id: usize) -> anyhow::Result<Vec<Team>> {
+        sqlx::query(&format!("SELECT {TEAM_COLUMNS} WHERE teams.battle_id = ? ORDER BY teams.id"))
+            .bind(battle_id as i64)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(team_from_row)
+            .collect()
+    }
+
+    async fn upsert_battle(&self, battle: &BattleRecord) -> anyhow::Result<()> {
+        let players = battle.players.iter().map(Principal::to_text).collect::<Vec<_>>();
+        sqlx::query(
+            "INSERT INTO battles \
+            (id, creator, deposit_price, service_fee, players, initial_state, finished, updated_at) \
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+            ON CONFLICT (id) DO UPDATE SET \
+            creator = excluded.creator, deposit_price = excluded.deposit_price, \
+            service_fee = excluded.service_fee, players = excluded.players, \
+            initial_state = excluded.initial_state, finished = excluded.finished, \
+            updated_at = excluded.updated_at",
+        )
+        .bind(battle.id as i64)
+        .bind(battle.creator.to_text())
+        .bind(battle.deposit_price.to_string())
+        .bind(battle.service_fee.to_string())
+        .bind(serde_json::to_string(&players)?)
+        .bind(battle.initial_state.as_ref().map(serde_json::to_string).transpose()?)
+        .bind(battle.finished)
+        .bind(battle.updated_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_battle(&self, id: usize) -> anyhow::Result<Option<BattleRecord>> {
+        sqlx::query("SELECT * FROM battles WHERE id = ?")
+            .bind(id as i64)
+            .fetch_optional(&self.pool)
+            .await?
+            .as_ref()
+            .map(battle_from_row)
+            .transpose()
+    }
+
+    async fn list_unfinished_battles(&self) -> anyhow::Result<Vec<BattleRecord>> {
+        sqlx::query("SELECT * FROM battles WHERE finished = 0 ORDER BY id")
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(battle_from_row)
+            .collect()
+    }
+
+    async fn last_battle_id(&self) -> anyhow::Result<Option<usize>> {
+        let id: Option<i64> = sqlx::query_scalar("SELECT MAX(id) FROM battles")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(id.map(|id| id as usize))
+    }
+
+    async fn insert_proof_job(&self, job: &ProofJob) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO proof_jobs \
+            (id, battle_id, owner, status, initial_state, solution, proof, public_values, error, created_at, updated_at) \
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&job.id)
+        .bind(job.battle_id.map(|id| id as i64))
+        .bind(job.owner.map(|owner| owner.to_text()))
+        .bind(job.status.as_str())
+        .bind(serde_json::to_string(&job.initial_state)?)
+        .bind(serde_json::to_string(&job.solution)?)
+        .bind(&job.proof)
+        .bind(&job.public_values)
+        .bind(&job.error)
+        .bind(job.created_at)
+        .bind(job.updated_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn update_proof_job(&self, job: &ProofJob) -> anyhow::Result<()> {
+        let res = sqlx::query(
+            "UPDATE proof_jobs SET status = ?, proof = ?, public_values = ?, error = ?, updated_at = ? \
+            WHERE id = ?",
+        )
+        .bind(job.status.as_str())
+        .bind(&job.proof)
+        .bind(&job.public_values)
+        .bind(&job.error)
+        .bind(job.updated_at)
+        .bind(&job.id)
+        .execute(&self.pool)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(anyhow!("Proof job {} does not exist", job.id));
+        }
+        Ok(())
+    }
+
+    async fn get_proof_job(&self, id: &str) -> anyhow::Result<Option<ProofJob>> {
+        sqlx::query("SELECT * FROM proof_jobs WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .as_ref()
+            .map(proof_job_from_row)
+            .transpose()
+    }
+
+    async fn list_unfinished_proof_jobs(&self) -> anyhow::Result<Vec<ProofJob>> {
+        sqlx::query("SELECT * FROM proof_jobs WHERE status IN (?, ?) ORDER BY created_at, rowid")
+            .bind(ProofJobStatus::Queued.as_str())
+            .bind(ProofJobStatus::Running.as_str())
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(proof_job_from_row)
+            .collect()
+    }
+
+    async fn insert_submission(&self, submission: &Submission) -> anyhow::Result<i64> {
+        let id = sqlx::query(
+            "INSERT INTO submissions \
+            (battle_id, team_id, public, proof_job_id, player_contributions, status, error, created_at) \
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(submission.battle_id as i64)
+        .bind(submission.team_id)
+        .bind(submission.public)
+        .bind(&submission.proof_job_id)
+        .bind(serde_json::to_string(&submission.player_contributions)?)
+        .bind(submission.status.as_str())
+        .bind(&submission.error)
+        .bind(submission.created_at)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    async fn update_submission(&self, submission: &Submission) -> anyhow::Result<()> {
+        let res = sqlx::query("UPDATE submissions SET status = ?, error = ? WHERE id = ?")
+            .bind(submission.status.as_str())
+            .bind(&submission.error)
+            .bind(submission.id)
+            .execute(&self.pool)
+            .await?;
+        if res.rows_affected() == 0 {
+            return Err(anyhow!("Submission {} does not exist", submission.id));
+        }
+        Ok(())
+    }
+
+    async fn get_submission_by_proof_job(&self, proof_job_id: &str) -> anyhow::Result<Option<Submission>> {
+        sqlx::query("SELECT * FROM submissions WHERE proof_job_id = ?")
+            .bind(proof_job_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .as_ref()
+            .map(submission_from_row)
+            .transpose()
+    }
+
+    async fn list_submissions(&self, battle_id: usize) -> anyhow::Result<Vec<Submission>> {
+        sqlx::query("SELECT * FROM submissions WHERE battle_id = ? ORDER BY id")
+            .bind(battle_id as i64)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(submission_from_row)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::sqlite::SqliteStorage;
+    use crate::storage::{
+        now, BattleRecord, ProofJob, ProofJobStatus, Storage, Submission, SubmissionStatus,
+        TeamContext,
+    };
+    use ic_agent::export::Principal;
+    use std::str::FromStr;
+    use sudoku::game::PlayerContribution;
+
+    fn player() -> Principal {
+        Principal::from_str("pnklf-ojyec-2l2al-nuxfq-3vss6-udii2-2txam-wusja-usc54-n5uos-gae").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_teams() {
+        let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+        let context = TeamContext {
+            context_id: "context".to_string(),
+            context_identity: "identity".to_string(),
+            application_id: "application".to_string(),
+        };
+        let team = storage.create_team(Some(4), player(), context.clone()).await.unwrap();
+
+        assert_eq!(storage.get_team(team.id).await.unwrap(), Some(team.clone()));
+        assert_eq!(storage.get_team_by_context("context").await.unwrap(), Some(team.clone()));
+        assert_eq!(storage.list_teams(4).await.unwrap(), vec![team]);
+        assert!(storage.list_teams(5).await.unwrap().is_empty());
+        // a context belongs to a single team
+        assert!(storage.create_team(None, player(), context).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_battles_and_submissions() {
+        let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+        let mut battle = BattleRecord {
+            id: 4,
+            creator: player(),
+            deposit_price: u128::MAX,
+            service_fee: 100,
+            players: vec![],
+            initial_state: None,
+            finished: false,
+            updated_at: now(),
+        };
+        storage.upsert_battle(&battle).await.unwrap();
+        assert_eq!(storage.list_unfinished_battles().await.unwrap(), vec![battle.clone()]);
+        battle.players.push(player());
+        battle.initial_state = Some(vec![(0, 8), (1, 7)]);
+        storage.upsert_battle(&battle).await.unwrap();
+        assert_eq!(storage.get_battle(4).await.unwrap(), Some(battle));
+        assert_eq!(storage.get_battle(5).await.unwrap(), None);
+        assert_eq!(storage.last_battle_id().await.unwrap(), Some(4));
+
+        let mut job = ProofJob::new(Some(4), player(), vec![(0, 8)], vec![1, 2, 3]);
+        storage.insert_proof_job(&job).await.unwrap();
+        job.status = ProofJobStatus::Succeeded;
+        job.proof = Some("00".to_string());
+        storage.update_proof_job(&job).await.unwrap();
+        assert_eq!(storage.get_proof_job(&job.id).await.unwrap(), Some(job.clone()));
+        let queued = ProofJob::new(None, player(), vec![], vec![]);
+        storage.insert_proof_job(&queued).await.unwrap();
+        assert_eq!(storage.list_unfinished_proof_jobs().await.unwrap(), vec![queued]);
+
+        let mut submission = Submission {
+            id: 0,
+            battle_id: 4,
+            team_id: None,
+            public: false,
+            proof_job_id: Some(job.id.clone()),
+            player_contributions: vec![PlayerContribution {
+                player: player(),
+                percent: 100.0,
+            }],
+            status: SubmissionStatus::Pending,
+            error: None,
+            created_at: now(),
+        };
+        submission.id = storage.insert_submission(&submission).await.unwrap();
+        submission.status = SubmissionStatus::Accepted;
+        storage.update_submission(&submission).await.unwrap();
+        let found = storage.get_submission_by_proof_job(&job.id).await.unwrap().unwrap();
+        assert_eq!(found.status, SubmissionStatus::Accepted);
+
+        let submissions = storage.list_submissions(4).await.unwrap();
+        assert_eq!(submissions.len(), 1);
+        assert_eq!(submissions[0].id, submission.id);
+        assert_eq!(submissions[0].proof_job_id, submission.proof_job_id);
+        assert_eq!(submissions[0].player_contributions[0].player, player());
+    }
+}